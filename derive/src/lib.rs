@@ -0,0 +1,391 @@
+//! Derive macros of `wasmedge_plugin_sdk`, re-exported by it with the `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitInt, LitStr};
+
+// WasmEdge keeps the low 24 bits of the code of a user error.
+const MAX_USER_CODE: u32 = 0x00FF_FFFF;
+
+/// Implements `HostErrorCode` for an enum, whose every variant has a stable code given by
+/// `#[host_error(code = N)]`.
+///
+/// `#[host_error(into = "user")]` on the enum, the default, makes the error fail the host
+/// function with `CoreError::User(code)` and implements `From` into `CoreError`. WasmEdge keeps
+/// only the low 24 bits of a user error, so `N` is from 1 to `0xFF_FFFF`.
+/// `#[host_error(into = "return")]` makes it returned to the guest as `-code` and implements
+/// `From` into `i32`, with `N` from 1 to `i32::MAX`.
+///
+/// Variants may have fields, but `from_code` only decodes the variants without fields and returns
+/// `None` for the codes of the others.
+#[proc_macro_derive(HostError, attributes(host_error))]
+pub fn derive_host_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match host_error(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn host_error(input: &DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            input,
+            "HostError can only be derived for enums",
+        ));
+    };
+
+    let mut into_return = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("host_error"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("into") {
+                let value: LitStr = meta.value()?.parse()?;
+                into_return = match value.value().as_str() {
+                    "user" => false,
+                    "return" => true,
+                    _ => return Err(meta.error("expected \"user\" or \"return\"")),
+                };
+                Ok(())
+            } else {
+                Err(meta.error("unknown attribute, expected `into`"))
+            }
+        })?;
+    }
+
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        let mut code = None;
+        for attr in variant
+            .attrs
+            .iter()
+            .filter(|a| a.path().is_ident("host_error"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("code") {
+                    let lit: LitInt = meta.value()?.parse()?;
+                    let value: u32 = lit.base10_parse()?;
+                    if into_return && (value == 0 || value > i32::MAX as u32) {
+                        return Err(Error::new_spanned(
+                            &lit,
+                            "the code must be from 1 to i32::MAX",
+                        ));
+                    }
+                    if !into_return && (value == 0 || value > MAX_USER_CODE) {
+                        return Err(Error::new_spanned(
+                            &lit,
+                            "the code of a user error must be from 1 to 0xFF_FFFF, the bits \
+                             WasmEdge keeps",
+                        ));
+                    }
+                    code = Some(value);
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown attribute, expected `code`"))
+                }
+            })?;
+        }
+        let Some(code) = code else {
+            return Err(Error::new_spanned(
+                variant,
+                "missing `#[host_error(code = N)]`, which keeps the code stable",
+            ));
+        };
+        if let Some((other, _)) = variants.iter().find(|(_, c)| *c == code) {
+            return Err(Error::new_spanned(
+                variant,
+                format!("the code {code} is already used by `{other}`"),
+            ));
+        }
+        variants.push((&variant.ident, code));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let sdk = quote!(::wasmedge_plugin_sdk::error);
+
+    let code_arms = data
+        .variants
+        .iter()
+        .zip(&variants)
+        .map(|(variant, (ident, code))| {
+            let pattern = match &variant.fields {
+                Fields::Unit => quote!(Self::#ident),
+                Fields::Unnamed(_) => quote!(Self::#ident(..)),
+                Fields::Named(_) => quote!(Self::#ident { .. }),
+            };
+            quote!(#pattern => #code,)
+        });
+    let decode_arms = data
+        .variants
+        .iter()
+        .zip(&variants)
+        .filter(|(variant, _)| matches!(variant.fields, Fields::Unit))
+        .map(|(_, (ident, code))| quote!(#code => Some(Self::#ident),));
+    let codes = variants.iter().map(|(ident, code)| {
+        let ident = LitStr::new(&ident.to_string(), Span::call_site());
+        quote!((#ident, #code),)
+    });
+    let kind = if into_return {
+        quote!(#sdk::HostErrorKind::Return)
+    } else {
+        quote!(#sdk::HostErrorKind::User)
+    };
+    let conversion = if into_return {
+        quote! {
+            impl #impl_generics ::std::convert::From<#name #ty_generics> for i32 #where_clause {
+                fn from(e: #name #ty_generics) -> i32 {
+                    #sdk::HostErrorCode::return_value(&e)
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl #impl_generics ::std::convert::From<#name #ty_generics> for #sdk::CoreError
+            #where_clause
+            {
+                fn from(e: #name #ty_generics) -> #sdk::CoreError {
+                    #sdk::CoreError::User(#sdk::HostErrorCode::code(&e))
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics #sdk::HostErrorCode for #name #ty_generics #where_clause {
+            const KIND: #sdk::HostErrorKind = #kind;
+            const CODES: &'static [(&'static str, u32)] = &[#(#codes)*];
+
+            fn code(&self) -> u32 {
+                match self {
+                    #(#code_arms)*
+                }
+            }
+
+            fn from_code(code: u32) -> ::std::option::Option<Self> {
+                match code {
+                    #(#decode_arms)*
+                    _ => None,
+                }
+            }
+        }
+
+        #conversion
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn error_of(input: DeriveInput) -> String {
+        host_error(&input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn expands_codes_and_decodes_unit_variants_only() {
+        let input: DeriveInput = parse_quote! {
+            #[host_error(into = "return")]
+            enum TextError {
+                #[host_error(code = 1)]
+                Empty,
+                #[host_error(code = 2)]
+                TooLong(usize),
+                #[host_error(code = 3)]
+                Invalid { at: usize },
+            }
+        };
+        let expected = quote! {
+            impl ::wasmedge_plugin_sdk::error::HostErrorCode for TextError {
+                const KIND: ::wasmedge_plugin_sdk::error::HostErrorKind =
+                    ::wasmedge_plugin_sdk::error::HostErrorKind::Return;
+                const CODES: &'static [(&'static str, u32)] =
+                    &[("Empty", 1u32), ("TooLong", 2u32), ("Invalid", 3u32),];
+
+                fn code(&self) -> u32 {
+                    match self {
+                        Self::Empty => 1u32,
+                        Self::TooLong(..) => 2u32,
+                        Self::Invalid { .. } => 3u32,
+                    }
+                }
+
+                fn from_code(code: u32) -> ::std::option::Option<Self> {
+                    match code {
+                        1u32 => Some(Self::Empty),
+                        _ => None,
+                    }
+                }
+            }
+
+            impl ::std::convert::From<TextError> for i32 {
+                fn from(e: TextError) -> i32 {
+                    ::wasmedge_plugin_sdk::error::HostErrorCode::return_value(&e)
+                }
+            }
+        };
+        assert_eq!(
+            host_error(&input).unwrap().to_string(),
+            expected.to_string()
+        );
+    }
+
+    #[test]
+    fn converts_user_errors_into_core_error() {
+        let input: DeriveInput = parse_quote! {
+            enum IoError {
+                #[host_error(code = 7)]
+                Closed,
+            }
+        };
+        let tokens = host_error(&input).unwrap().to_string();
+        let kind = quote!(::wasmedge_plugin_sdk::error::HostErrorKind::User).to_string();
+        let from = quote! {
+            impl ::std::convert::From<IoError> for ::wasmedge_plugin_sdk::error::CoreError
+        };
+        assert!(tokens.contains(&kind));
+        assert!(tokens.contains(&from.to_string()));
+    }
+
+    #[test]
+    fn checks_the_code_range_of_user_errors() {
+        let input: DeriveInput = parse_quote! {
+            enum E {
+                #[host_error(code = 0xFF_FFFF)]
+                Last,
+            }
+        };
+        assert!(host_error(&input).is_ok());
+
+        let out_of_range = "the code of a user error must be from 1 to 0xFF_FFFF, the bits \
+                            WasmEdge keeps";
+        let input: DeriveInput = parse_quote! {
+            #[host_error(into = "user")]
+            enum E {
+                #[host_error(code = 0x100_0000)]
+                TooBig,
+            }
+        };
+        assert_eq!(error_of(input), out_of_range);
+        let input: DeriveInput = parse_quote! {
+            enum E {
+                #[host_error(code = 0)]
+                Zero,
+            }
+        };
+        assert_eq!(error_of(input), out_of_range);
+    }
+
+    #[test]
+    fn checks_the_code_range_of_returned_errors() {
+        let input: DeriveInput = parse_quote! {
+            #[host_error(into = "return")]
+            enum E {
+                #[host_error(code = 0x100_0000)]
+                Wide,
+                #[host_error(code = 2147483647)]
+                Last,
+            }
+        };
+        assert!(host_error(&input).is_ok());
+
+        let out_of_range = "the code must be from 1 to i32::MAX";
+        let input: DeriveInput = parse_quote! {
+            #[host_error(into = "return")]
+            enum E {
+                #[host_error(code = 2147483648)]
+                TooBig,
+            }
+        };
+        assert_eq!(error_of(input), out_of_range);
+        let input: DeriveInput = parse_quote! {
+            #[host_error(into = "return")]
+            enum E {
+                #[host_error(code = 0)]
+                Zero,
+            }
+        };
+        assert_eq!(error_of(input), out_of_range);
+    }
+
+    #[test]
+    fn rejects_duplicate_codes() {
+        let input: DeriveInput = parse_quote! {
+            enum E {
+                #[host_error(code = 1)]
+                First,
+                #[host_error(code = 2)]
+                Second,
+                #[host_error(code = 1)]
+                Third(u8),
+            }
+        };
+        assert_eq!(error_of(input), "the code 1 is already used by `First`");
+    }
+
+    #[test]
+    fn rejects_a_missing_code() {
+        let input: DeriveInput = parse_quote! {
+            enum E {
+                #[host_error(code = 1)]
+                First,
+                Second,
+            }
+        };
+        assert_eq!(
+            error_of(input),
+            "missing `#[host_error(code = N)]`, which keeps the code stable"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_attribute_keys() {
+        let input: DeriveInput = parse_quote! {
+            #[host_error(kind = "user")]
+            enum E {
+                #[host_error(code = 1)]
+                First,
+            }
+        };
+        assert_eq!(error_of(input), "unknown attribute, expected `into`");
+
+        let input: DeriveInput = parse_quote! {
+            #[host_error(into = "trap")]
+            enum E {
+                #[host_error(code = 1)]
+                First,
+            }
+        };
+        assert_eq!(error_of(input), "expected \"user\" or \"return\"");
+
+        let input: DeriveInput = parse_quote! {
+            enum E {
+                #[host_error(code = 1, name = "first")]
+                First,
+            }
+        };
+        assert_eq!(error_of(input), "unknown attribute, expected `code`");
+    }
+
+    #[test]
+    fn rejects_non_enums() {
+        let input: DeriveInput = parse_quote! {
+            struct E {
+                code: u32,
+            }
+        };
+        assert_eq!(error_of(input), "HostError can only be derived for enums");
+
+        let input: DeriveInput = parse_quote! {
+            union E {
+                code: u32,
+            }
+        };
+        assert_eq!(error_of(input), "HostError can only be derived for enums");
+    }
+}
@@ -1,16 +1,20 @@
 use std::io::Write;
 
 use wasmedge_plugin_sdk::{
-    error::CoreError,
+    error::{CoreError, HostError, HostErrorCode},
     memory::Memory,
     module::{PluginModule, SyncInstanceRef},
     types::{ValType, WasmVal},
 };
 
-#[derive(Debug)]
+#[derive(Debug, HostError)]
+#[host_error(into = "return")]
 pub enum PluginError {
+    #[host_error(code = 1)]
     ParamError,
+    #[host_error(code = 2)]
     MemoryError,
+    #[host_error(code = 3)]
     UTF8Error,
 }
 
@@ -45,9 +49,7 @@ pub fn create_module() -> PluginModule<()> {
 
         match to_uppercase_(main_memory, &args[0], &args[1]) {
             Ok(_) => Ok(vec![WasmVal::I32(0)]),
-            Err(PluginError::ParamError) => Ok(vec![WasmVal::I32(-1)]),
-            Err(PluginError::MemoryError) => Ok(vec![WasmVal::I32(-2)]),
-            Err(PluginError::UTF8Error) => Ok(vec![WasmVal::I32(-3)]),
+            Err(e) => e.into_result(),
         }
     }
 
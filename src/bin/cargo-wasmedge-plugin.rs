@@ -0,0 +1,371 @@
+//! Inspects and installs WasmEdge plugins without loading them into the runtime.
+//!
+//! ```text
+//! cargo wasmedge-plugin inspect <plugin.so> [--exports] [--json]
+//! cargo wasmedge-plugin install <plugin.so> [--dir <plugin dir>] [--force]
+//! ```
+//!
+//! Both commands load the library and call `WasmEdge_Plugin_GetDescriptor`, which runs the
+//! `on_load` hook of a plugin built with this SDK, and unloading the library runs its
+//! `on_unload` hook. `inspect --exports` also creates every module, which runs the create
+//! functions and the `on_create` and `on_finalize` hooks. Only inspect plugins you trust.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use wasmedge_plugin_sdk::manifest::{validate_descriptor, PluginManifest};
+use wasmedge_plugin_sdk::plugin::ffi;
+
+const USAGE: &str = "\
+Usage:
+    cargo wasmedge-plugin inspect <plugin> [--exports] [--json]
+    cargo wasmedge-plugin install <plugin> [--dir <plugin dir>] [--force]
+
+inspect  Prints the descriptor of a plugin and checks it.
+         --exports  creates every module to list its functions, memories and globals,
+                    which runs the module code of the plugin
+         --json     prints the manifest as JSON
+install  Checks a plugin and copies it into the plugin directory, which defaults to
+         $WASMEDGE_PLUGIN_PATH or ~/.wasmedge/plugin.
+         --force    installs a plugin even if the checks fail
+
+Loading a plugin runs its load hook, and unloading it runs its unload hook.";
+
+type GetDescriptor = unsafe extern "C" fn() -> *const ffi::WasmEdge_PluginDescriptor;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Args {
+    command: String,
+    plugin: PathBuf,
+    exports: bool,
+    json: bool,
+    force: bool,
+    dir: Option<PathBuf>,
+}
+
+/// Parses the arguments after the name of the binary.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut args = args.into_iter().peekable();
+    // Cargo passes the name of the subcommand first.
+    if args.peek().map(String::as_str) == Some("wasmedge-plugin") {
+        args.next();
+    }
+    let command = args.next().ok_or("missing a command")?;
+    let mut parsed = Args {
+        command,
+        plugin: PathBuf::new(),
+        exports: false,
+        json: false,
+        force: false,
+        dir: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--exports" => parsed.exports = true,
+            "--json" => parsed.json = true,
+            "--force" => parsed.force = true,
+            "--dir" => parsed.dir = Some(args.next().ok_or("missing the value of --dir")?.into()),
+            "-h" | "--help" => return Err(String::new()),
+            _ if arg.starts_with('-') => return Err(format!("unknown flag `{arg}`")),
+            _ if parsed.plugin.as_os_str().is_empty() => parsed.plugin = arg.into(),
+            _ => return Err(format!("unexpected argument `{arg}`")),
+        }
+    }
+    if parsed.plugin.as_os_str().is_empty() {
+        return Err("missing the path of the plugin".to_string());
+    }
+    Ok(parsed)
+}
+
+/// Loads a plugin library and returns it with its descriptor.
+fn load(
+    path: &Path,
+) -> Result<(libloading::Library, *const ffi::WasmEdge_PluginDescriptor), String> {
+    unsafe {
+        let lib = libloading::Library::new(path)
+            .map_err(|e| format!("fail to load `{}`: {e}", path.display()))?;
+        let get_descriptor = *lib
+            .get::<GetDescriptor>(b"WasmEdge_Plugin_GetDescriptor\0")
+            .map_err(|e| format!("`{}` isn't a WasmEdge plugin: {e}", path.display()))?;
+        let desc = get_descriptor();
+        Ok((lib, desc))
+    }
+}
+
+/// Prints the problems of a descriptor and returns `true` if there are none.
+fn check(desc: *const ffi::WasmEdge_PluginDescriptor) -> bool {
+    let errors = unsafe { validate_descriptor(desc) };
+    for e in &errors {
+        eprintln!("error: {e}");
+    }
+    errors.is_empty()
+}
+
+fn print_manifest(manifest: &PluginManifest) {
+    println!("{} {}", manifest.name, manifest.version);
+    if !manifest.description.is_empty() {
+        println!("  {}", manifest.description);
+    }
+    println!("API version: {}", manifest.api_version);
+    println!("Modules:");
+    for module in &manifest.modules {
+        println!("  {}: {}", module.name, module.description);
+        for line in module.exports.to_string().lines() {
+            println!("    {line}");
+        }
+    }
+    println!("Options:");
+    for option in &manifest.options {
+        let default = option.default.as_deref().unwrap_or("none");
+        println!(
+            "  --{} <{}> (default: {}): {}",
+            option.name, option.ty, default, option.description
+        );
+    }
+}
+
+/// Reads the manifest of a descriptor, creating every module to list its exports if `exports`
+/// is set, and returns it with `false` if a module failed to be created.
+///
+/// If a module fails to be created, the error is printed and the manifest is read without
+/// exports, so the rest of the plugin is still described.
+fn read_manifest(
+    desc: *const ffi::WasmEdge_PluginDescriptor,
+    exports: bool,
+) -> Result<(PluginManifest, bool), String> {
+    if exports {
+        match unsafe { PluginManifest::from_descriptor(desc) } {
+            Ok(manifest) => return Ok((manifest, true)),
+            Err(e) => eprintln!("error: fail to create the modules to list their exports: {e}"),
+        }
+    }
+    let manifest =
+        unsafe { PluginManifest::read_descriptor(desc) }.ok_or("the descriptor is null")?;
+    Ok((manifest, !exports))
+}
+
+fn inspect(args: &Args) -> Result<bool, String> {
+    let (_lib, desc) = load(&args.plugin)?;
+    let valid = check(desc);
+    if desc.is_null() {
+        return Ok(false);
+    }
+    let (manifest, created) = read_manifest(desc, args.exports && valid)?;
+    if args.json {
+        println!("{}", manifest.to_json());
+    } else {
+        print_manifest(&manifest);
+    }
+    Ok(valid && created)
+}
+
+fn plugin_dir(args: &Args) -> Result<PathBuf, String> {
+    if let Some(dir) = &args.dir {
+        return Ok(dir.clone());
+    }
+    if let Some(dir) = std::env::var_os("WASMEDGE_PLUGIN_PATH") {
+        return Ok(dir.into());
+    }
+    let home = std::env::var_os("HOME").ok_or("neither --dir nor $HOME is set")?;
+    Ok(Path::new(&home).join(".wasmedge").join("plugin"))
+}
+
+fn install(args: &Args) -> Result<bool, String> {
+    let valid = {
+        let (_lib, desc) = load(&args.plugin)?;
+        check(desc)
+    };
+    if !valid && !args.force {
+        return Err("the plugin isn't installed, pass --force to install it anyway".to_string());
+    }
+    let dir = plugin_dir(args)?;
+    let file_name = args
+        .plugin
+        .file_name()
+        .ok_or("the plugin path has no file name")?;
+    let target = dir.join(file_name);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("fail to create `{}`: {e}", dir.display()))?;
+    std::fs::copy(&args.plugin, &target)
+        .map_err(|e| format!("fail to copy to `{}`: {e}", target.display()))?;
+    println!("Installed {}", target.display());
+    Ok(valid)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("error: {e}\n");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let result = match args.command.as_str() {
+        "inspect" => inspect(&args),
+        "install" => install(&args),
+        command => Err(format!("unknown command `{command}`\n\n{USAGE}")),
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use wasmedge_plugin_sdk::error::CoreError;
+    use wasmedge_plugin_sdk::memory::Memory;
+    use wasmedge_plugin_sdk::module::{PluginModule, SyncInstanceRef};
+    use wasmedge_plugin_sdk::plugin::{ModuleCreateContext, PluginBuilder};
+    use wasmedge_plugin_sdk::types::WasmVal;
+
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parses_inspect_flags() {
+        let args = parse(&["inspect", "libp.so", "--exports", "--json"]).unwrap();
+        assert_eq!(
+            args,
+            Args {
+                command: "inspect".to_string(),
+                plugin: "libp.so".into(),
+                exports: true,
+                json: true,
+                force: false,
+                dir: None,
+            }
+        );
+    }
+
+    #[test]
+    fn skips_the_cargo_subcommand_name() {
+        let args = parse(&["wasmedge-plugin", "install", "--force", "libp.so"]).unwrap();
+        assert_eq!(args.command, "install");
+        assert_eq!(args.plugin, PathBuf::from("libp.so"));
+        assert!(args.force);
+    }
+
+    #[test]
+    fn parses_install_dir() {
+        let args = parse(&["install", "libp.so", "--dir", "/plugins"]).unwrap();
+        assert_eq!(args.dir, Some(PathBuf::from("/plugins")));
+        assert_eq!(
+            parse(&["install", "libp.so", "--dir"]).unwrap_err(),
+            "missing the value of --dir"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(parse(&[]).unwrap_err(), "missing a command");
+        assert_eq!(
+            parse(&["inspect"]).unwrap_err(),
+            "missing the path of the plugin"
+        );
+        assert_eq!(
+            parse(&["inspect", "libp.so", "--all"]).unwrap_err(),
+            "unknown flag `--all`"
+        );
+        assert_eq!(
+            parse(&["inspect", "a.so", "b.so"]).unwrap_err(),
+            "unexpected argument `b.so`"
+        );
+        assert_eq!(parse(&["inspect", "--help"]).unwrap_err(), "");
+    }
+
+    enum Level {
+        Low,
+        High,
+    }
+
+    impl FromStr for Level {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "low" => Ok(Level::Low),
+                "high" => Ok(Level::High),
+                _ => Err(format!("unknown level `{s}`")),
+            }
+        }
+    }
+
+    fn noop(
+        _inst: &mut SyncInstanceRef,
+        _mem: &mut Memory,
+        _data: &mut (),
+        _args: Vec<WasmVal>,
+    ) -> Result<Vec<WasmVal>, CoreError> {
+        Ok(vec![])
+    }
+
+    fn create_leveled(ctx: &ModuleCreateContext) -> *mut ffi::WasmEdge_ModuleInstanceContext {
+        let name = match ctx.options().parse::<Level>("level") {
+            Ok(Level::Low) => "low",
+            Ok(Level::High) => "high",
+            Err(_) => return std::ptr::null_mut(),
+        };
+        let mut module = PluginModule::create(ctx.module_name(), ()).unwrap();
+        module.add_func(name, "() -> ()", noop).unwrap();
+        module.into()
+    }
+
+    fn create_nothing() -> *mut ffi::WasmEdge_ModuleInstanceContext {
+        std::ptr::null_mut()
+    }
+
+    #[test]
+    fn inspects_the_exports_of_a_plugin_with_options() {
+        let plugin = PluginBuilder::new("leveled", "a plugin with options")
+            .module("leveled", "exports a function per level", create_leveled)
+            .enum_option::<Level>("level", "the level", "low")
+            .option("retries", "the number of retries", 3u32)
+            .build();
+        let desc = plugin.descriptor();
+        assert!(check(desc));
+
+        let (manifest, created) = read_manifest(desc, true).unwrap();
+        assert!(created);
+        let funcs = &manifest.modules[0].exports.funcs;
+        assert_eq!(
+            funcs.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(),
+            ["last_error", "low"]
+        );
+        let defaults = manifest
+            .options
+            .iter()
+            .map(|o| (o.name.as_str(), o.default.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(defaults, [("level", Some("low")), ("retries", Some("3"))]);
+    }
+
+    #[test]
+    fn describes_a_plugin_whose_modules_fail_to_be_created() {
+        let plugin = PluginBuilder::new("broken", "a plugin whose module fails")
+            .module("broken", "never created", create_nothing)
+            .option("retries", "the number of retries", 3u32)
+            .build();
+
+        let (manifest, created) = read_manifest(plugin.descriptor(), true).unwrap();
+        assert!(!created);
+        assert_eq!(manifest.modules[0].name, "broken");
+        assert!(manifest.modules[0].exports.funcs.is_empty());
+        assert_eq!(manifest.options[0].name, "retries");
+
+        let (_, created) = read_manifest(plugin.descriptor(), false).unwrap();
+        assert!(created);
+    }
+}
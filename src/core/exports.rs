@@ -0,0 +1,230 @@
+//! Defines the structured report of the exports of a module instance.
+
+use std::fmt;
+
+use super::types::{fmt_func_type, ValType, WasmVal};
+
+/// An exported function and its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuncExport {
+    pub name: String,
+    pub params: Vec<ValType>,
+    pub returns: Vec<ValType>,
+}
+
+/// An exported memory and its limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryExport {
+    pub name: String,
+    pub min: u32,
+    pub max: Option<u32>,
+    pub shared: bool,
+    /// The current size of the memory in pages (64 KiB of each page).
+    pub pages: u32,
+}
+
+/// An exported global, its type and its current value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlobalExport {
+    pub name: String,
+    pub ty: ValType,
+    pub mutable: bool,
+    pub val: WasmVal,
+}
+
+/// An exported table, its reference type and its limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TableExport {
+    pub name: String,
+    pub ty: ValType,
+    pub min: u32,
+    pub max: Option<u32>,
+}
+
+/// Every export of a module instance together with its type.
+///
+/// Each list is sorted by name, so two reports of compatible modules compare and print the same.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExportReport {
+    pub funcs: Vec<FuncExport>,
+    pub memories: Vec<MemoryExport>,
+    pub globals: Vec<GlobalExport>,
+    pub tables: Vec<TableExport>,
+}
+
+impl ExportReport {
+    pub(crate) fn sort(&mut self) {
+        self.funcs.sort_by(|a, b| a.name.cmp(&b.name));
+        self.memories.sort_by(|a, b| a.name.cmp(&b.name));
+        self.globals.sort_by(|a, b| a.name.cmp(&b.name));
+        self.tables.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.funcs.is_empty()
+            && self.memories.is_empty()
+            && self.globals.is_empty()
+            && self.tables.is_empty()
+    }
+}
+
+fn fmt_max(max: Option<u32>) -> String {
+    match max {
+        Some(max) => max.to_string(),
+        None => "none".to_string(),
+    }
+}
+
+impl fmt::Display for FuncExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "func {}: ", self.name)?;
+        fmt_func_type(f, &self.params, &self.returns)
+    }
+}
+
+impl fmt::Display for MemoryExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "memory {}: min={} max={} shared={} pages={}",
+            self.name,
+            self.min,
+            fmt_max(self.max),
+            self.shared,
+            self.pages
+        )
+    }
+}
+
+impl fmt::Display for GlobalExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mutability = if self.mutable { "mut" } else { "const" };
+        write!(
+            f,
+            "global {}: {} {} = {:?}",
+            self.name, mutability, self.ty, self.val
+        )
+    }
+}
+
+impl fmt::Display for TableExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "table {}: {} min={} max={}",
+            self.name,
+            self.ty,
+            self.min,
+            fmt_max(self.max)
+        )
+    }
+}
+
+impl fmt::Display for ExportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for func in &self.funcs {
+            writeln!(f, "{func}")?;
+        }
+        for mem in &self.memories {
+            writeln!(f, "{mem}")?;
+        }
+        for global in &self.globals {
+            writeln!(f, "{global}")?;
+        }
+        for table in &self.tables {
+            writeln!(f, "{table}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn func(name: &str, params: Vec<ValType>, returns: Vec<ValType>) -> FuncExport {
+        FuncExport {
+            name: name.to_string(),
+            params,
+            returns,
+        }
+    }
+
+    fn report() -> ExportReport {
+        ExportReport {
+            funcs: vec![
+                func("sub", vec![ValType::I32, ValType::I32], vec![ValType::I32]),
+                func("add", vec![ValType::I64, ValType::I64], vec![ValType::I64]),
+                func("reset", vec![], vec![]),
+            ],
+            memories: vec![MemoryExport {
+                name: "memory".to_string(),
+                min: 1,
+                max: None,
+                shared: false,
+                pages: 2,
+            }],
+            globals: vec![
+                GlobalExport {
+                    name: "limit".to_string(),
+                    ty: ValType::I32,
+                    mutable: false,
+                    val: WasmVal::I32(7),
+                },
+                GlobalExport {
+                    name: "counter".to_string(),
+                    ty: ValType::I64,
+                    mutable: true,
+                    val: WasmVal::I64(0),
+                },
+            ],
+            tables: vec![TableExport {
+                name: "table".to_string(),
+                ty: ValType::FuncRef,
+                min: 1,
+                max: Some(4),
+            }],
+        }
+    }
+
+    #[test]
+    fn sorts_every_list_by_name() {
+        let mut a = report();
+        a.sort();
+        let names: Vec<_> = a.funcs.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["add", "reset", "sub"]);
+        let names: Vec<_> = a.globals.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["counter", "limit"]);
+
+        let mut b = report();
+        b.funcs.reverse();
+        b.globals.reverse();
+        b.sort();
+        assert_eq!(a, b);
+        assert_eq!(a.to_string(), b.to_string());
+    }
+
+    #[test]
+    fn prints_one_export_per_line() {
+        let mut report = report();
+        report.sort();
+        assert_eq!(
+            report.to_string(),
+            "func add: (i64, i64) -> i64\n\
+             func reset: () -> ()\n\
+             func sub: (i32, i32) -> i32\n\
+             memory memory: min=1 max=none shared=false pages=2\n\
+             global counter: mut i64 = I64(0)\n\
+             global limit: const i32 = I32(7)\n\
+             table table: funcref min=1 max=4\n"
+        );
+        assert!(!report.is_empty());
+        assert!(ExportReport::default().is_empty());
+        assert_eq!(ExportReport::default().to_string(), "");
+    }
+}
@@ -1,8 +1,8 @@
 use std::os::raw::c_void;
 
 use crate::core::executor::Executor;
-use crate::core::types::{ValType, WasmVal};
-use crate::error::CoreError;
+use crate::core::types::{fmt_func_type, parse_func_type, ValType, WasmVal};
+use crate::error::{CoreError, ParseTypeError};
 use wasmedge_sys::ffi;
 
 pub type FnWrapper = unsafe extern "C" fn(
@@ -36,7 +36,6 @@ impl Function {
                 data.cast(),
                 0,
             );
-            ty.delete();
             if ctx.is_null() {
                 None
             } else {
@@ -55,9 +54,7 @@ impl Function {
         if ty.is_null() {
             None
         } else {
-            let ty = FuncType {
-                inner: InnerFuncType(ty),
-            };
+            let ty = FuncType::borrowed(ty);
             Some((
                 ty.params_type_iter().collect(),
                 ty.returns_type_iter().collect(),
@@ -71,9 +68,7 @@ impl Function {
         if ty.is_null() {
             None
         } else {
-            let ty = FuncType {
-                inner: InnerFuncType(ty),
-            };
+            let ty = FuncType::borrowed(ty);
             Some(ty.params_len() as usize)
         }
     }
@@ -84,9 +79,7 @@ impl Function {
         if ty.is_null() {
             None
         } else {
-            let ty = FuncType {
-                inner: InnerFuncType(ty),
-            };
+            let ty = FuncType::borrowed(ty);
             Some(ty.returns_len() as usize)
         }
     }
@@ -107,11 +100,20 @@ impl Clone for InnerFunc {
 unsafe impl Send for InnerFunc {}
 unsafe impl Sync for InnerFunc {}
 
+/// A WasmEdge function type, deleted on drop unless it is borrowed from a function instance.
 #[derive(Debug)]
 pub struct FuncType {
     pub(crate) inner: InnerFuncType,
+    owned: bool,
 }
 impl FuncType {
+    pub(crate) fn borrowed(ctx: *const ffi::WasmEdge_FunctionTypeContext) -> Self {
+        Self {
+            inner: InnerFuncType(ctx),
+            owned: false,
+        }
+    }
+
     pub fn create<I: IntoIterator<Item = ValType>, R: IntoIterator<Item = ValType>>(
         args: I,
         returns: R,
@@ -138,10 +140,19 @@ impl FuncType {
         } else {
             Some(Self {
                 inner: InnerFuncType(ctx),
+                owned: true,
             })
         }
     }
 
+    /// Returns the parameter and result types.
+    pub fn signature(&self) -> (Vec<ValType>, Vec<ValType>) {
+        (
+            self.params_type_iter().collect(),
+            self.returns_type_iter().collect(),
+        )
+    }
+
     pub fn params_len(&self) -> u32 {
         unsafe { ffi::WasmEdge_FunctionTypeGetParametersLength(self.inner.0) }
     }
@@ -171,9 +182,57 @@ impl FuncType {
 
         types.into_iter().map(Into::into)
     }
+}
+
+impl Drop for FuncType {
+    fn drop(&mut self) {
+        if self.owned {
+            unsafe { ffi::WasmEdge_FunctionTypeDelete(self.inner.0 as *mut _) };
+        }
+    }
+}
 
-    pub(crate) fn delete(self) {
-        unsafe { ffi::WasmEdge_FunctionTypeDelete(self.inner.0 as *mut _) };
+impl std::fmt::Display for FuncType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params = self.params_type_iter().collect::<Vec<_>>();
+        let returns = self.returns_type_iter().collect::<Vec<_>>();
+        fmt_func_type(f, &params, &returns)
+    }
+}
+
+impl std::str::FromStr for FuncType {
+    type Err = ParseTypeError;
+
+    /// Parses a function type written as `(i32, i32) -> i32` or as `(param i32 i32) (result i32)`,
+    /// where a parameter can be named as in `(param $len i32)`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (params, returns) = parse_func_type(s)?;
+        FuncType::create(params, returns)
+            .ok_or_else(|| ParseTypeError::new(s, "fail to create FuncType context"))
+    }
+}
+
+/// A function type given to add a host function: a `(params, returns)` tuple, a [FuncType], or
+/// text in one of the forms parsed by [FuncType]'s `FromStr`.
+pub trait IntoFuncSignature {
+    fn into_signature(self) -> Result<(Vec<ValType>, Vec<ValType>), ParseTypeError>;
+}
+
+impl IntoFuncSignature for (Vec<ValType>, Vec<ValType>) {
+    fn into_signature(self) -> Result<(Vec<ValType>, Vec<ValType>), ParseTypeError> {
+        Ok(self)
+    }
+}
+
+impl IntoFuncSignature for &FuncType {
+    fn into_signature(self) -> Result<(Vec<ValType>, Vec<ValType>), ParseTypeError> {
+        Ok(self.signature())
+    }
+}
+
+impl IntoFuncSignature for &str {
+    fn into_signature(self) -> Result<(Vec<ValType>, Vec<ValType>), ParseTypeError> {
+        parse_func_type(self)
     }
 }
 
@@ -199,9 +258,7 @@ impl FuncRef {
         if ty.is_null() {
             None
         } else {
-            let ty = FuncType {
-                inner: InnerFuncType(ty),
-            };
+            let ty = FuncType::borrowed(ty);
             Some((
                 ty.params_type_iter().collect(),
                 ty.returns_type_iter().collect(),
@@ -214,9 +271,7 @@ impl FuncRef {
         if ty.is_null() {
             None
         } else {
-            let ty = FuncType {
-                inner: InnerFuncType(ty),
-            };
+            let ty = FuncType::borrowed(ty);
             Some(ty.params_len() as usize)
         }
     }
@@ -226,9 +281,7 @@ impl FuncRef {
         if ty.is_null() {
             None
         } else {
-            let ty = FuncType {
-                inner: InnerFuncType(ty),
-            };
+            let ty = FuncType::borrowed(ty);
             Some(ty.returns_len() as usize)
         }
     }
@@ -1,18 +1,25 @@
 use crate::core::types::WasmVal;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ConstGlobal {
     pub name: String,
     pub val: WasmVal,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MutGlobal {
     pub name: String,
     pub val: WasmVal,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum Global {
     Const(ConstGlobal),
     Mut(MutGlobal),
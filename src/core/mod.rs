@@ -1,5 +1,10 @@
 pub mod config;
 pub mod executor;
+pub mod exports;
 pub mod instance;
 pub mod module;
+#[cfg(feature = "serde")]
+mod serde_impls;
+pub mod store;
 pub mod types;
+pub mod version;
@@ -1,10 +1,13 @@
 //! Defines WasmEdge Instance and other relevant types.
 
+use std::sync::Mutex;
+
 use wasmedge_sys::ffi;
 
 use crate::{core::types::WasmVal, error::InstanceError};
 
 use super::{
+    exports::{ExportReport, FuncExport, GlobalExport, MemoryExport, TableExport},
     instance::{function::FuncRef, memory::Memory},
     instance::{
         function::InnerFunc,
@@ -13,6 +16,9 @@ use super::{
     types::WasmEdgeString,
 };
 
+#[cfg(feature = "wasm_ref")]
+use super::types::GuestObjects;
+
 pub(crate) trait AsInnerInstance {
     unsafe fn get_mut_ptr(&self) -> *mut ffi::WasmEdge_ModuleInstanceContext;
 }
@@ -84,23 +90,74 @@ pub trait AsInstance {
     fn get_all_exports_globals(&self) -> Vec<Global>;
 
     fn set_global(&mut self, global: MutGlobal) -> Result<(), InstanceError>;
+
+    /// Returns every export of this module instance together with its type.
+    fn exports(&self) -> ExportReport;
 }
 
 #[derive(Debug)]
 pub struct ImportModule<T: Sized + Send> {
     pub inner: InnerInstance,
     pub data_ptr: *mut T,
+    host_data: *mut HostData<T>,
+}
+
+/// The result of a lifecycle hook of a module.
+pub type HookResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+/// A hook run on the host data right before WasmEdge drops it with its module instance.
+pub type FinalizeHook<T> = fn(&mut T) -> HookResult;
+
+/// The host data attached to the module instance of an [ImportModule].
+struct HostData<T: Sized + Send> {
+    inst: *mut ffi::WasmEdge_ModuleInstanceContext,
+    name: String,
+    on_finalize: Option<FinalizeHook<T>>,
+    // Keys of the host functions which must live as long as the module instance.
+    func_keys: Vec<Box<dyn Send>>,
+    // Host objects passed to the guest, freed with the module instance.
+    #[cfg(feature = "wasm_ref")]
+    guest_objects: GuestObjects,
+    data: T,
+}
+
+/// Returns the guest objects of the module instance whose host data holds `data`.
+///
+/// # Safety
+///
+/// `data` must be the data pointer of an [ImportModule] whose module instance is alive.
+#[cfg(feature = "wasm_ref")]
+pub(crate) unsafe fn guest_objects_of<T: Sized + Send>(data: *mut T) -> *const GuestObjects {
+    let host_data = data
+        .byte_sub(std::mem::offset_of!(HostData<T>, data))
+        .cast::<HostData<T>>();
+    &(*host_data).guest_objects
 }
 
 unsafe extern "C" fn host_data_finalizer<T: Sized + Send>(raw: *mut ::std::os::raw::c_void) {
-    let host_data: Box<T> = Box::from_raw(raw as *mut T);
+    let mut host_data: Box<HostData<T>> = Box::from_raw(raw as *mut HostData<T>);
+    unregister_instance(host_data.inst);
+    clear_last_error(&host_data.data as *const T as usize);
+    if let Some(on_finalize) = host_data.on_finalize {
+        if let Err(e) = on_finalize(&mut host_data.data) {
+            log::error!("Fail to finalize module `{}`: {}", host_data.name, e);
+        }
+    }
     drop(host_data);
 }
 
 impl<T: Sized + Send> ImportModule<T> {
     pub fn create<S: AsRef<str>>(name: S, data: T) -> Result<Self, InstanceError> {
         let raw_name = WasmEdgeString::new(name.as_ref())?;
-        let host_data = Box::leak(Box::new(data)) as *mut T;
+        let host_data = Box::leak(Box::new(HostData {
+            inst: std::ptr::null_mut(),
+            name: name.as_ref().to_string(),
+            on_finalize: None,
+            func_keys: Vec::new(),
+            #[cfg(feature = "wasm_ref")]
+            guest_objects: GuestObjects::default(),
+            data,
+        })) as *mut HostData<T>;
         let ctx = unsafe {
             ffi::WasmEdge_ModuleInstanceCreateWithData(
                 raw_name.as_raw(),
@@ -114,12 +171,101 @@ impl<T: Sized + Send> ImportModule<T> {
                 unsafe { host_data_finalizer::<T>(host_data as _) };
                 Err(InstanceError::CreateImportModule)
             }
-            false => Ok(Self {
-                inner: InnerInstance(ctx),
-                data_ptr: host_data,
-            }),
+            false => unsafe {
+                (*host_data).inst = ctx;
+                register_instance(name.as_ref(), ctx);
+                Ok(Self {
+                    inner: InnerInstance(ctx),
+                    data_ptr: &mut (*host_data).data,
+                    host_data,
+                })
+            },
         }
     }
+
+    /// Sets the hook run on the host data right before it is dropped with the module instance.
+    ///
+    /// An error returned by the hook is reported through the log.
+    pub fn on_finalize(&mut self, hook: FinalizeHook<T>) {
+        unsafe { (*self.host_data).on_finalize = Some(hook) };
+    }
+
+    /// Removes the hook set by [on_finalize](Self::on_finalize), e.g. when the host data was
+    /// never fully set up.
+    pub(crate) fn clear_on_finalize(&mut self) {
+        unsafe { (*self.host_data).on_finalize = None };
+    }
+
+    /// Keeps the key of a host function alive until the module instance is dropped.
+    pub(crate) fn keep_func_key(&mut self, key: Box<dyn Send>) {
+        unsafe { (*self.host_data).func_keys.push(key) };
+    }
+}
+
+/// The module instances created by this SDK which are still alive, by name.
+///
+/// WasmEdge doesn't expose the store of a calling frame, so without an attached store these are
+/// the modules a host function can reach besides its own. An instance is removed by its
+/// finalizer before WasmEdge frees it.
+static LIVE_INSTANCES: Mutex<Vec<(String, InnerInstance)>> = Mutex::new(Vec::new());
+
+fn register_instance(name: &str, ctx: *mut ffi::WasmEdge_ModuleInstanceContext) {
+    if let Ok(mut instances) = LIVE_INSTANCES.lock() {
+        instances.push((name.to_string(), InnerInstance(ctx)));
+    }
+}
+
+fn unregister_instance(ctx: *mut ffi::WasmEdge_ModuleInstanceContext) {
+    if let Ok(mut instances) = LIVE_INSTANCES.lock() {
+        instances.retain(|(_, inst)| inst.0 != ctx);
+    }
+}
+
+/// The last error message of each module instance created by this SDK, by the address of its
+/// host data.
+static LAST_ERRORS: Mutex<Vec<(usize, String)>> = Mutex::new(Vec::new());
+
+/// Sets the last error message of the module instance whose host data is at `data`.
+pub(crate) fn set_last_error(data: usize, message: String) {
+    if let Ok(mut errors) = LAST_ERRORS.lock() {
+        match errors.iter_mut().find(|(addr, _)| *addr == data) {
+            Some((_, last)) => *last = message,
+            None => errors.push((data, message)),
+        }
+    }
+}
+
+/// Returns the last error message of the module instance whose host data is at `data`.
+pub(crate) fn last_error(data: usize) -> Option<String> {
+    let errors = LAST_ERRORS.lock().ok()?;
+    errors
+        .iter()
+        .find(|(addr, _)| *addr == data)
+        .map(|(_, message)| message.clone())
+}
+
+fn clear_last_error(data: usize) {
+    if let Ok(mut errors) = LAST_ERRORS.lock() {
+        errors.retain(|(addr, _)| *addr != data);
+    }
+}
+
+/// Returns the names of the live module instances created by this SDK.
+pub(crate) fn live_instance_names() -> Vec<String> {
+    match LIVE_INSTANCES.lock() {
+        Ok(instances) => instances.iter().map(|(name, _)| name.clone()).collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Returns the most recently created live module instance with the given name.
+pub(crate) fn find_live_instance(name: &str) -> Option<*mut ffi::WasmEdge_ModuleInstanceContext> {
+    let instances = LIVE_INSTANCES.lock().ok()?;
+    instances
+        .iter()
+        .rev()
+        .find(|(inst_name, _)| inst_name == name)
+        .map(|(_, inst)| inst.0)
 }
 
 impl<T: Sized + Send> AsInnerInstance for ImportModule<T> {
@@ -306,9 +452,97 @@ impl<T: AsInnerInstance> AsInstance for T {
         }
         Ok(())
     }
+
+    fn exports(&self) -> ExportReport {
+        let mut report = ExportReport::default();
+
+        for name in self.func_names().unwrap_or_default() {
+            if let Some((params, returns)) = self.get_func(&name).ok().and_then(|f| f.func_type()) {
+                report.funcs.push(FuncExport {
+                    name,
+                    params,
+                    returns,
+                });
+            }
+        }
+
+        for (name, mem) in self.get_all_exports_memories() {
+            if let Some((min, max, shared)) = mem.get_type() {
+                report.memories.push(MemoryExport {
+                    name,
+                    min,
+                    max,
+                    shared,
+                    pages: mem.page_size(),
+                });
+            }
+        }
+
+        unsafe {
+            let module = self.get_mut_ptr();
+
+            let globals_num = ffi::WasmEdge_ModuleInstanceListGlobalLength(module);
+            let mut global_names = Vec::with_capacity(globals_num as usize);
+            let len = ffi::WasmEdge_ModuleInstanceListGlobal(
+                module,
+                global_names.as_mut_ptr(),
+                globals_num,
+            );
+            global_names.set_len(len as usize);
+
+            for name in global_names {
+                let global_ctx = ffi::WasmEdge_ModuleInstanceFindGlobal(module, name);
+                if global_ctx.is_null() {
+                    continue;
+                }
+                let global_type = ffi::WasmEdge_GlobalInstanceGetGlobalType(global_ctx);
+                if global_type.is_null() {
+                    continue;
+                }
+                if let Ok(name) = name.try_as_string() {
+                    report.globals.push(GlobalExport {
+                        name,
+                        ty: ffi::WasmEdge_GlobalTypeGetValType(global_type).into(),
+                        mutable: ffi::WasmEdge_Mutability_Const
+                            != ffi::WasmEdge_GlobalTypeGetMutability(global_type),
+                        val: ffi::WasmEdge_GlobalInstanceGetValue(global_ctx).into(),
+                    });
+                }
+            }
+
+            let tables_num = ffi::WasmEdge_ModuleInstanceListTableLength(module);
+            let mut table_names = Vec::with_capacity(tables_num as usize);
+            let len =
+                ffi::WasmEdge_ModuleInstanceListTable(module, table_names.as_mut_ptr(), tables_num);
+            table_names.set_len(len as usize);
+
+            for name in table_names {
+                let table_ctx = ffi::WasmEdge_ModuleInstanceFindTable(module, name);
+                if table_ctx.is_null() {
+                    continue;
+                }
+                let table_type = ffi::WasmEdge_TableInstanceGetTableType(table_ctx);
+                if table_type.is_null() {
+                    continue;
+                }
+                let limit = ffi::WasmEdge_TableTypeGetLimit(table_type);
+                if let Ok(name) = name.try_as_string() {
+                    report.tables.push(TableExport {
+                        name,
+                        ty: ffi::WasmEdge_TableTypeGetRefType(table_type).into(),
+                        min: limit.Min,
+                        max: if limit.HasMax { Some(limit.Max) } else { None },
+                    });
+                }
+            }
+        }
+
+        report.sort();
+        report
+    }
 }
 
-trait TryAsString {
+pub(crate) trait TryAsString {
     fn try_as_string(&self) -> Result<String, std::str::Utf8Error>;
 }
 
@@ -0,0 +1,233 @@
+//! Implements Serialize and Deserialize for WasmVal.
+//!
+//! A value is written as its type and its value, e.g. `{"type": "i32", "value": 42}`. Floats are
+//! written as numbers when they are finite and as a hex string of their bits otherwise, and both
+//! forms are read back, so every float round-trips bit-exactly, NaN payloads included. A `v128`
+//! is a hex string of its bits. A null reference is written as `null` with or without the
+//! `wasm_ref` feature. References can't be restored from their address, so only null references
+//! are read back.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use wasmedge_sys::ffi;
+
+use super::types::{ValType, WasmVal};
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+enum WasmValRepr {
+    I32(i32),
+    I64(i64),
+    F32(FloatRepr),
+    F64(FloatRepr),
+    V128(String),
+    FuncRef(Option<String>),
+    ExternRef(Option<String>),
+    UnsupportedRef(Option<String>),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum FloatRepr {
+    Number(f64),
+    Bits(String),
+}
+
+fn to_hex(bits: u128, digits: usize) -> String {
+    format!("0x{:0width$x}", bits, width = digits)
+}
+
+fn from_hex<E: de::Error>(s: &str) -> Result<u128, E> {
+    let digits = s.strip_prefix("0x").ok_or_else(|| {
+        E::custom(format!(
+            "expected a hex string starting with 0x, found `{s}`"
+        ))
+    })?;
+    u128::from_str_radix(digits, 16).map_err(E::custom)
+}
+
+fn null_ref<E: de::Error>(ty: ValType, addr: Option<String>) -> Result<WasmVal, E> {
+    match addr {
+        None => Ok(WasmVal::default_for(ty)),
+        Some(addr) => Err(E::custom(format!(
+            "a non-null {ty} ({addr}) can't be deserialized"
+        ))),
+    }
+}
+
+impl Serialize for WasmVal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self {
+            WasmVal::I32(v) => WasmValRepr::I32(*v),
+            WasmVal::I64(v) => WasmValRepr::I64(*v),
+            WasmVal::F32(v) if v.is_finite() => WasmValRepr::F32(FloatRepr::Number(*v as f64)),
+            WasmVal::F32(v) => WasmValRepr::F32(FloatRepr::Bits(to_hex(v.to_bits() as u128, 8))),
+            WasmVal::F64(v) if v.is_finite() => WasmValRepr::F64(FloatRepr::Number(*v)),
+            WasmVal::F64(v) => WasmValRepr::F64(FloatRepr::Bits(to_hex(v.to_bits() as u128, 16))),
+            WasmVal::V128(v) => WasmValRepr::V128(to_hex(*v as u128, 32)),
+            #[cfg(feature = "wasm_ref")]
+            WasmVal::FuncRef(f) => {
+                WasmValRepr::FuncRef(f.as_ref().map(|f| to_hex(f.inner.0 as usize as u128, 0)))
+            }
+            #[cfg(feature = "wasm_ref")]
+            WasmVal::ExternRef(r) => {
+                WasmValRepr::ExternRef(r.as_ref().map(|r| to_hex(r.as_raw() as usize as u128, 0)))
+            }
+            WasmVal::UnknownType(v) => {
+                // Without `wasm_ref` references stay raw, and null ones must still be `null`.
+                let addr = if unsafe { ffi::WasmEdge_ValueIsNullRef(*v) } {
+                    None
+                } else {
+                    Some(to_hex(v.Value, 0))
+                };
+                match ValType::from(v.Type) {
+                    ValType::FuncRef => WasmValRepr::FuncRef(addr),
+                    ValType::ExternRef => WasmValRepr::ExternRef(addr),
+                    _ => WasmValRepr::UnsupportedRef(addr),
+                }
+            }
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for WasmVal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let val = match WasmValRepr::deserialize(deserializer)? {
+            WasmValRepr::I32(v) => WasmVal::I32(v),
+            WasmValRepr::I64(v) => WasmVal::I64(v),
+            WasmValRepr::F32(FloatRepr::Number(v)) => WasmVal::F32(v as f32),
+            WasmValRepr::F32(FloatRepr::Bits(bits)) => {
+                let bits =
+                    u32::try_from(from_hex::<D::Error>(&bits)?).map_err(de::Error::custom)?;
+                WasmVal::F32(f32::from_bits(bits))
+            }
+            WasmValRepr::F64(FloatRepr::Number(v)) => WasmVal::F64(v),
+            WasmValRepr::F64(FloatRepr::Bits(bits)) => {
+                let bits =
+                    u64::try_from(from_hex::<D::Error>(&bits)?).map_err(de::Error::custom)?;
+                WasmVal::F64(f64::from_bits(bits))
+            }
+            WasmValRepr::V128(bits) => WasmVal::V128(from_hex::<D::Error>(&bits)? as i128),
+            WasmValRepr::FuncRef(addr) => null_ref(ValType::FuncRef, addr)?,
+            WasmValRepr::ExternRef(addr) => null_ref(ValType::ExternRef, addr)?,
+            WasmValRepr::UnsupportedRef(addr) => null_ref(ValType::UnsupportedRef, addr)?,
+        };
+        Ok(val)
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::core::instance::global::{ConstGlobal, Global, MutGlobal};
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(v: &T) -> T {
+        let json = serde_json::to_string(v).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn f32_bits(v: &WasmVal) -> u32 {
+        match v {
+            WasmVal::F32(v) => v.to_bits(),
+            v => panic!("expected an f32, found {v:?}"),
+        }
+    }
+
+    fn f64_bits(v: &WasmVal) -> u64 {
+        match v {
+            WasmVal::F64(v) => v.to_bits(),
+            v => panic!("expected an f64, found {v:?}"),
+        }
+    }
+
+    #[test]
+    fn round_trips_f32_bits() {
+        let values = [
+            f32::from_bits(0x7fc0_1234),
+            f32::from_bits(0xff80_0001),
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            -0.0,
+            0.0,
+            f32::from_bits(1),
+            f32::MAX,
+            1.1,
+        ];
+        for v in values {
+            let back = round_trip(&WasmVal::F32(v));
+            assert_eq!(f32_bits(&back), v.to_bits(), "{v:?}");
+        }
+    }
+
+    #[test]
+    fn round_trips_f64_bits() {
+        let values = [
+            f64::from_bits(0x7ff8_0000_dead_beef),
+            f64::from_bits(0xfff0_0000_0000_0001),
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            -0.0,
+            f64::from_bits(1),
+            f64::MIN,
+            0.1,
+        ];
+        for v in values {
+            let back = round_trip(&WasmVal::F64(v));
+            assert_eq!(f64_bits(&back), v.to_bits(), "{v:?}");
+        }
+    }
+
+    #[test]
+    fn round_trips_v128() {
+        for v in [0x0123_4567_89ab_cdef_fedc_ba98_7654_3210, -1, i128::MIN, 0] {
+            assert_eq!(round_trip(&WasmVal::V128(v)), WasmVal::V128(v));
+        }
+        assert_eq!(
+            serde_json::to_string(&WasmVal::V128(-1)).unwrap(),
+            format!(r#"{{"type":"v128","value":"0x{}"}}"#, "f".repeat(32))
+        );
+    }
+
+    #[test]
+    fn round_trips_null_refs() {
+        for ty in [ValType::FuncRef, ValType::ExternRef] {
+            let null = WasmVal::default_for(ty);
+            let json = serde_json::to_string(&null).unwrap();
+            assert_eq!(json, format!(r#"{{"type":"{ty}","value":null}}"#));
+            let back: WasmVal = serde_json::from_str(&json).unwrap();
+            assert_eq!(back.ty(), ty);
+            assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        }
+        let non_null = r#"{"type":"externref","value":"0x10"}"#;
+        assert!(serde_json::from_str::<WasmVal>(non_null).is_err());
+    }
+
+    #[test]
+    fn round_trips_globals() {
+        let nan = f64::from_bits(0x7ff8_0000_0000_0042);
+        let global = Global::Mut(MutGlobal {
+            name: "counter".to_string(),
+            val: WasmVal::F64(nan),
+        });
+        match round_trip(&global) {
+            Global::Mut(MutGlobal { name, val }) => {
+                assert_eq!(name, "counter");
+                assert_eq!(f64_bits(&val), nan.to_bits());
+            }
+            global => panic!("expected a mutable global, found {global:?}"),
+        }
+
+        let global = Global::Const(ConstGlobal {
+            name: "limit".to_string(),
+            val: WasmVal::I64(-7),
+        });
+        match round_trip(&global) {
+            Global::Const(ConstGlobal { name, val }) => {
+                assert_eq!(name, "limit");
+                assert_eq!(val, WasmVal::I64(-7));
+            }
+            global => panic!("expected a const global, found {global:?}"),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Defines WasmEdge Store and the lookup of the module instances registered in it.
+
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+use wasmedge_sys::ffi;
+
+use crate::error::InstanceError;
+
+use super::{
+    module::{AsInnerInstance, InnerInstance, TryAsString},
+    types::WasmEdgeString,
+};
+
+/// A borrowed WasmEdge store, which holds the named module instances registered by the runtime.
+#[derive(Debug, Clone, Copy)]
+pub struct StoreRef<'s> {
+    inner: *const ffi::WasmEdge_StoreContext,
+    _store: PhantomData<&'s ffi::WasmEdge_StoreContext>,
+}
+
+impl<'s> StoreRef<'s> {
+    /// Wraps a raw store context owned by the runtime.
+    ///
+    /// # Safety
+    ///
+    /// `ctx` must point to a valid store which outlives `'s`.
+    pub unsafe fn from_raw(ctx: *const ffi::WasmEdge_StoreContext) -> Self {
+        Self {
+            inner: ctx,
+            _store: PhantomData,
+        }
+    }
+
+    /// Returns the length of the named module instances registered in this store.
+    pub fn module_len(&self) -> u32 {
+        unsafe { ffi::WasmEdge_StoreListModuleLength(self.inner) }
+    }
+
+    /// Returns the names of the module instances registered in this store.
+    pub fn module_names(&self) -> Option<Vec<String>> {
+        let len_mod_names = self.module_len();
+        if len_mod_names > 0 {
+            let mut mod_names = Vec::with_capacity(len_mod_names as usize);
+            unsafe {
+                let len = ffi::WasmEdge_StoreListModule(
+                    self.inner,
+                    mod_names.as_mut_ptr(),
+                    len_mod_names,
+                );
+                mod_names.set_len(len as usize);
+            }
+
+            let names = mod_names
+                .into_iter()
+                .map(|x| {
+                    let r: Result<String, std::str::Utf8Error> = x.try_as_string();
+                    r.unwrap_or_default()
+                })
+                .collect::<Vec<String>>();
+            Some(names)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the module instance registered in this store by name.
+    ///
+    /// # Error
+    ///
+    /// If fail to find the target module instance, then an error is returned.
+    pub fn find_module(&self, name: impl AsRef<str>) -> Result<ModuleRef<'s>, InstanceError> {
+        let mod_name = WasmEdgeString::new(name.as_ref())?;
+        let ctx = unsafe { ffi::WasmEdge_StoreFindModule(self.inner, mod_name.as_raw()) };
+        if ctx.is_null() {
+            Err(InstanceError::NotFoundModule(name.as_ref().to_string()))
+        } else {
+            Ok(unsafe { ModuleRef::from_raw(ctx.cast_mut()) })
+        }
+    }
+}
+
+unsafe impl Send for StoreRef<'_> {}
+unsafe impl Sync for StoreRef<'_> {}
+
+/// The stores attached to executors by the embedder, by the addresses of the executor and the
+/// store.
+static ATTACHED_STORES: Mutex<Vec<(usize, usize)>> = Mutex::new(Vec::new());
+
+/// Makes the module instances registered in `store` reachable from the host functions run by
+/// `executor`, through [SyncInstanceRef::store](crate::module::SyncInstanceRef::store) and
+/// [SyncInstanceRef::find_module](crate::module::SyncInstanceRef::find_module).
+///
+/// WasmEdge doesn't expose the store of a calling frame to host functions, so only an embedder
+/// which owns both contexts, e.g. one linking WasmEdge with the `standalone` feature, can attach
+/// it. Under the `wasmedge` CLI no store is attached, and the guest's WASI module and the
+/// modules of other plugins can't be reached.
+///
+/// # Safety
+///
+/// `store` must stay valid until [detach_store] is called with `executor`.
+pub unsafe fn attach_store(
+    executor: *const ffi::WasmEdge_ExecutorContext,
+    store: *const ffi::WasmEdge_StoreContext,
+) {
+    if let Ok(mut stores) = ATTACHED_STORES.lock() {
+        stores.retain(|(exec, _)| *exec != executor as usize);
+        stores.push((executor as usize, store as usize));
+    }
+}
+
+/// Detaches the store attached to `executor` by [attach_store].
+pub fn detach_store(executor: *const ffi::WasmEdge_ExecutorContext) {
+    if let Ok(mut stores) = ATTACHED_STORES.lock() {
+        stores.retain(|(exec, _)| *exec != executor as usize);
+    }
+}
+
+/// Returns the store attached to `executor`.
+///
+/// # Safety
+///
+/// The store must stay attached during `'s`.
+pub(crate) unsafe fn attached_store<'s>(
+    executor: *const ffi::WasmEdge_ExecutorContext,
+) -> Option<StoreRef<'s>> {
+    let stores = ATTACHED_STORES.lock().ok()?;
+    let (_, store) = stores.iter().find(|(exec, _)| *exec == executor as usize)?;
+    Some(StoreRef::from_raw(
+        *store as *const ffi::WasmEdge_StoreContext,
+    ))
+}
+
+/// A borrowed module instance found through a [StoreRef] or a calling frame.
+///
+/// It exposes the same lookup of functions, memories and globals as the calling module instance,
+/// and can't outlive the store or the calling frame it was found through.
+#[derive(Debug)]
+pub struct ModuleRef<'a> {
+    inst: InnerInstance,
+    _module: PhantomData<&'a ffi::WasmEdge_ModuleInstanceContext>,
+}
+
+impl ModuleRef<'_> {
+    pub(crate) unsafe fn from_raw(ctx: *mut ffi::WasmEdge_ModuleInstanceContext) -> Self {
+        Self {
+            inst: InnerInstance::from_raw(ctx),
+            _module: PhantomData,
+        }
+    }
+
+    /// Returns the name of this module instance.
+    pub fn name(&self) -> Option<String> {
+        unsafe {
+            let name = ffi::WasmEdge_ModuleInstanceGetModuleName(self.inst.get_mut_ptr());
+            name.try_as_string().ok()
+        }
+    }
+}
+
+impl AsInnerInstance for ModuleRef<'_> {
+    unsafe fn get_mut_ptr(&self) -> *mut ffi::WasmEdge_ModuleInstanceContext {
+        self.inst.get_mut_ptr()
+    }
+}
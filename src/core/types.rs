@@ -1,8 +1,18 @@
 use std::{ffi::CString, fmt::Debug};
 
+#[cfg(feature = "wasm_ref")]
+use std::{
+    any::Any,
+    cell::Cell,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
 #[cfg(feature = "wasm_ref")]
 use super::instance::function::{FuncRef, InnerFunc};
 
+use crate::error::{ParseTypeError, ValTypeMismatch};
+
 use wasmedge_sys::ffi;
 
 /// Struct of WasmEdge String.
@@ -67,9 +77,185 @@ pub enum WasmVal {
     F32(f32),
     F64(f64),
     V128(i128),
+    /// A reference to a function, `None` for a null `funcref`.
+    #[cfg(feature = "wasm_ref")]
+    FuncRef(Option<FuncRef>),
+    /// A reference to a host object, `None` for a null `externref`.
+    #[cfg(feature = "wasm_ref")]
+    ExternRef(Option<ExternRef>),
     UnknownType(ffi::WasmEdge_Value),
 }
 
+#[cfg(feature = "wasm_ref")]
+type HostObject = Box<dyn Any + Send + Sync>;
+
+/// The host objects passed to the guest by the host functions of a module instance, which keeps
+/// them alive until they are released or the module instance is dropped.
+#[cfg(feature = "wasm_ref")]
+#[derive(Default)]
+pub(crate) struct GuestObjects(Mutex<HashMap<usize, Arc<HostObject>>>);
+
+#[cfg(feature = "wasm_ref")]
+thread_local! {
+    // The guest objects of the module instance whose host function is running on this thread.
+    static CURRENT_GUEST_OBJECTS: Cell<*const GuestObjects> = const { Cell::new(std::ptr::null()) };
+}
+
+// Marks the guest objects of a running host function, and restores the outer ones when dropped.
+#[cfg(feature = "wasm_ref")]
+pub(crate) struct GuestObjectsGuard(*const GuestObjects);
+
+#[cfg(feature = "wasm_ref")]
+impl GuestObjectsGuard {
+    /// # Safety
+    ///
+    /// `objects` must stay valid until the guard is dropped.
+    pub(crate) unsafe fn enter(objects: *const GuestObjects) -> Self {
+        Self(CURRENT_GUEST_OBJECTS.replace(objects))
+    }
+}
+
+#[cfg(feature = "wasm_ref")]
+impl Drop for GuestObjectsGuard {
+    fn drop(&mut self) {
+        CURRENT_GUEST_OBJECTS.set(self.0);
+    }
+}
+
+// Runs `f` on the guest objects of the running host function, if any.
+#[cfg(feature = "wasm_ref")]
+fn with_guest_objects<R>(f: impl FnOnce(&mut HashMap<usize, Arc<HostObject>>) -> R) -> Option<R> {
+    let objects = CURRENT_GUEST_OBJECTS.get();
+    if objects.is_null() {
+        return None;
+    }
+    let mut objects = unsafe { &*objects }.0.lock().ok()?;
+    Some(f(&mut objects))
+}
+
+/// A reference to a host object, which the guest holds as an `externref`.
+///
+/// A host object created by [ExternRef::new] is reference-counted: every [ExternRef] to it holds
+/// a count, and passing it to the guest from a host function holds one more in the module
+/// instance of the function until [ExternRef::release_from_guest] or until the module instance
+/// is dropped. WasmEdge doesn't report when the guest drops a reference, so the guest's count has
+/// to be released by a host function, usually the one which closes the object.
+///
+/// The guest's counts are kept per module instance, so a raw `externref` is only resolved to a
+/// host object passed to the guest by the module instance of the running host function. Outside
+/// a host function, no count is held for the guest.
+#[cfg(feature = "wasm_ref")]
+#[derive(Clone)]
+pub struct ExternRef {
+    inner: ExternRefInner,
+}
+
+#[cfg(feature = "wasm_ref")]
+#[derive(Clone)]
+enum ExternRefInner {
+    Host(Arc<HostObject>),
+    Foreign(InnerExternRef),
+}
+
+#[cfg(feature = "wasm_ref")]
+impl ExternRef {
+    /// Wraps a host object into a reference which can be passed to the guest.
+    pub fn new<R: Any + Send + Sync>(obj: R) -> Self {
+        Self {
+            inner: ExternRefInner::Host(Arc::new(Box::new(obj))),
+        }
+    }
+
+    /// Wraps a raw `externref`.
+    ///
+    /// If `ptr` refers to a host object created by [ExternRef::new] which the guest still holds
+    /// through the module instance of the running host function, the returned reference shares
+    /// it. Otherwise it is an opaque foreign reference.
+    pub unsafe fn from_raw(ptr: *mut std::os::raw::c_void) -> Self {
+        let obj = with_guest_objects(|objects| objects.get(&(ptr as usize)).cloned()).flatten();
+        match obj {
+            Some(obj) => Self {
+                inner: ExternRefInner::Host(obj),
+            },
+            None => Self {
+                inner: ExternRefInner::Foreign(InnerExternRef(ptr)),
+            },
+        }
+    }
+
+    pub fn as_raw(&self) -> *mut std::os::raw::c_void {
+        match &self.inner {
+            ExternRefInner::Host(obj) => Arc::as_ptr(obj).cast_mut().cast(),
+            ExternRefInner::Foreign(inner) => inner.0,
+        }
+    }
+
+    /// Returns `true` if this refers to a host object of type `R`.
+    pub fn is<R: Any>(&self) -> bool {
+        self.downcast_ref::<R>().is_some()
+    }
+
+    /// Returns the host object if it is of type `R`.
+    pub fn downcast_ref<R: Any>(&self) -> Option<&R> {
+        match &self.inner {
+            ExternRefInner::Host(obj) => (***obj).downcast_ref::<R>(),
+            ExternRefInner::Foreign(_) => None,
+        }
+    }
+
+    /// Releases the count held by the guest since this host object was passed to it.
+    ///
+    /// Returns `false` if the guest doesn't hold this host object through the module instance of
+    /// the running host function.
+    pub fn release_from_guest(&self) -> bool {
+        with_guest_objects(|objects| objects.remove(&(self.as_raw() as usize)).is_some())
+            .unwrap_or(false)
+    }
+
+    // Holds the host object for the guest and returns the raw `externref`.
+    fn to_guest(&self) -> *mut std::os::raw::c_void {
+        if let ExternRefInner::Host(obj) = &self.inner {
+            with_guest_objects(|objects| {
+                objects
+                    .entry(self.as_raw() as usize)
+                    .or_insert_with(|| obj.clone());
+            });
+        }
+        self.as_raw()
+    }
+}
+
+#[cfg(feature = "wasm_ref")]
+impl Debug for ExternRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match &self.inner {
+            ExternRefInner::Host(_) => "Host",
+            ExternRefInner::Foreign(_) => "Foreign",
+        };
+        f.debug_struct("ExternRef")
+            .field("kind", &kind)
+            .field("ptr", &self.as_raw())
+            .finish()
+    }
+}
+
+#[cfg(feature = "wasm_ref")]
+impl PartialEq for ExternRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_raw() == other.as_raw()
+    }
+}
+#[cfg(feature = "wasm_ref")]
+impl Eq for ExternRef {}
+
+#[cfg(feature = "wasm_ref")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InnerExternRef(pub(crate) *mut std::os::raw::c_void);
+#[cfg(feature = "wasm_ref")]
+unsafe impl Send for InnerExternRef {}
+#[cfg(feature = "wasm_ref")]
+unsafe impl Sync for InnerExternRef {}
+
 impl PartialEq for WasmVal {
     fn eq(&self, other: &Self) -> bool {
         use WasmVal::*;
@@ -79,6 +265,12 @@ impl PartialEq for WasmVal {
             (F32(i), F32(other)) => *i == *other,
             (F64(i), F64(other)) => *i == *other,
             (V128(i), V128(other)) => *i == *other,
+            #[cfg(feature = "wasm_ref")]
+            (FuncRef(i), FuncRef(other)) => {
+                i.as_ref().map(|f| f.inner.0) == other.as_ref().map(|f| f.inner.0)
+            }
+            #[cfg(feature = "wasm_ref")]
+            (ExternRef(i), ExternRef(other)) => *i == *other,
             (UnknownType(..), UnknownType(..)) => false,
             _ => false,
         }
@@ -86,9 +278,165 @@ impl PartialEq for WasmVal {
 }
 impl Eq for WasmVal {}
 
+impl WasmVal {
+    /// Returns the type of this value.
+    pub fn ty(&self) -> ValType {
+        match self {
+            WasmVal::I32(_) => ValType::I32,
+            WasmVal::I64(_) => ValType::I64,
+            WasmVal::F32(_) => ValType::F32,
+            WasmVal::F64(_) => ValType::F64,
+            WasmVal::V128(_) => ValType::V128,
+            #[cfg(feature = "wasm_ref")]
+            WasmVal::FuncRef(_) => ValType::FuncRef,
+            #[cfg(feature = "wasm_ref")]
+            WasmVal::ExternRef(_) => ValType::ExternRef,
+            WasmVal::UnknownType(v) => v.Type.into(),
+        }
+    }
+
+    /// Returns the default value of a type: zero for numbers and vectors, null for references.
+    pub fn default_for(ty: ValType) -> Self {
+        match ty {
+            ValType::I32 => WasmVal::I32(0),
+            ValType::I64 => WasmVal::I64(0),
+            ValType::F32 => WasmVal::F32(0.0),
+            ValType::F64 => WasmVal::F64(0.0),
+            ValType::V128 => WasmVal::V128(0),
+            #[cfg(feature = "wasm_ref")]
+            ValType::FuncRef => WasmVal::FuncRef(None),
+            #[cfg(feature = "wasm_ref")]
+            ValType::ExternRef => WasmVal::ExternRef(None),
+            _ => WasmVal::UnknownType(unsafe { ffi::WasmEdge_ValueGenNullRef(ty.into()) }),
+        }
+    }
+}
+
+macro_rules! impl_v128_lanes {
+    ($($lane_ty:ty, $lanes:literal, $as_fn:ident, $from_fn:ident;)*) => {
+        /// Lane views of [WasmVal::V128].
+        ///
+        /// Lanes are in little-endian order as in the wasm spec: lane 0 is made of the least
+        /// significant bytes of the `i128`.
+        impl WasmVal {
+            $(
+                #[doc = concat!("Returns the lanes of a `V128` value as `", stringify!($lane_ty), "x", $lanes, "`.")]
+                pub fn $as_fn(&self) -> Option<[$lane_ty; $lanes]> {
+                    const LANE_SIZE: usize = std::mem::size_of::<$lane_ty>();
+                    match self {
+                        WasmVal::V128(v) => {
+                            let bytes = v.to_le_bytes();
+                            let mut lanes = [<$lane_ty>::default(); $lanes];
+                            for (lane, chunk) in lanes.iter_mut().zip(bytes.chunks_exact(LANE_SIZE)) {
+                                let mut lane_bytes = [0u8; LANE_SIZE];
+                                lane_bytes.copy_from_slice(chunk);
+                                *lane = <$lane_ty>::from_le_bytes(lane_bytes);
+                            }
+                            Some(lanes)
+                        }
+                        _ => None,
+                    }
+                }
+
+                #[doc = concat!("Creates a `V128` value from `", stringify!($lane_ty), "x", $lanes, "` lanes.")]
+                pub fn $from_fn(lanes: [$lane_ty; $lanes]) -> Self {
+                    const LANE_SIZE: usize = std::mem::size_of::<$lane_ty>();
+                    let mut bytes = [0u8; 16];
+                    for (chunk, lane) in bytes.chunks_exact_mut(LANE_SIZE).zip(lanes.iter()) {
+                        chunk.copy_from_slice(&lane.to_le_bytes());
+                    }
+                    WasmVal::V128(i128::from_le_bytes(bytes))
+                }
+            )*
+        }
+    };
+}
+
+impl_v128_lanes! {
+    i8, 16, as_i8x16, from_i8x16;
+    u8, 16, as_u8x16, from_u8x16;
+    i16, 8, as_i16x8, from_i16x8;
+    u16, 8, as_u16x8, from_u16x8;
+    i32, 4, as_i32x4, from_i32x4;
+    u32, 4, as_u32x4, from_u32x4;
+    i64, 2, as_i64x2, from_i64x2;
+    u64, 2, as_u64x2, from_u64x2;
+    f32, 4, as_f32x4, from_f32x4;
+    f64, 2, as_f64x2, from_f64x2;
+}
+
+macro_rules! impl_wasm_val_conversion {
+    ($($rust_ty:ty => $variant:ident as $inner_ty:ty),* $(,)?) => {
+        $(
+            impl From<$rust_ty> for WasmVal {
+                #[allow(clippy::unnecessary_cast)]
+                fn from(value: $rust_ty) -> Self {
+                    WasmVal::$variant(value as $inner_ty)
+                }
+            }
+
+            impl TryFrom<WasmVal> for $rust_ty {
+                type Error = ValTypeMismatch;
+
+                #[allow(clippy::unnecessary_cast)]
+                fn try_from(value: WasmVal) -> Result<Self, Self::Error> {
+                    match value {
+                        WasmVal::$variant(v) => Ok(v as $rust_ty),
+                        other => Err(ValTypeMismatch {
+                            expected: ValType::$variant,
+                            actual: other.ty(),
+                        }),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_wasm_val_conversion! {
+    i32 => I32 as i32,
+    u32 => I32 as i32,
+    i64 => I64 as i64,
+    u64 => I64 as i64,
+    f32 => F32 as f32,
+    f64 => F64 as f64,
+    i128 => V128 as i128,
+}
+
+impl From<bool> for WasmVal {
+    fn from(value: bool) -> Self {
+        WasmVal::I32(value as i32)
+    }
+}
+
+impl TryFrom<WasmVal> for bool {
+    type Error = ValTypeMismatch;
+
+    fn try_from(value: WasmVal) -> Result<Self, Self::Error> {
+        i32::try_from(value).map(|v| v != 0)
+    }
+}
+
 impl From<ffi::WasmEdge_Value> for WasmVal {
     fn from(raw_val: ffi::WasmEdge_Value) -> Self {
         unsafe {
+            #[cfg(feature = "wasm_ref")]
+            if ffi::WasmEdge_ValTypeIsFuncRef(raw_val.Type) {
+                if ffi::WasmEdge_ValueIsNullRef(raw_val) {
+                    return WasmVal::FuncRef(None);
+                }
+                return WasmVal::FuncRef(Some(FuncRef {
+                    inner: InnerFunc(ffi::WasmEdge_ValueGetFuncRef(raw_val)),
+                }));
+            } else if ffi::WasmEdge_ValTypeIsExternRef(raw_val.Type) {
+                if ffi::WasmEdge_ValueIsNullRef(raw_val) {
+                    return WasmVal::ExternRef(None);
+                }
+                return WasmVal::ExternRef(Some(ExternRef::from_raw(
+                    ffi::WasmEdge_ValueGetExternRef(raw_val),
+                )));
+            }
+
             if ffi::WasmEdge_ValTypeIsI32(raw_val.Type) {
                 return WasmVal::I32(ffi::WasmEdge_ValueGetI32(raw_val));
             } else if ffi::WasmEdge_ValTypeIsI64(raw_val.Type) {
@@ -122,6 +470,18 @@ impl Into<ffi::WasmEdge_Value> for WasmVal {
                 WasmVal::F32(n) => ffi::WasmEdge_ValueGenF32(n),
                 WasmVal::F64(n) => ffi::WasmEdge_ValueGenF64(n),
                 WasmVal::V128(n) => ffi::WasmEdge_ValueGenV128(n),
+                #[cfg(feature = "wasm_ref")]
+                WasmVal::FuncRef(Some(f)) => ffi::WasmEdge_ValueGenFuncRef(f.inner.0),
+                #[cfg(feature = "wasm_ref")]
+                WasmVal::FuncRef(None) => {
+                    ffi::WasmEdge_ValueGenNullRef(ffi::WasmEdge_ValTypeGenFuncRef())
+                }
+                #[cfg(feature = "wasm_ref")]
+                WasmVal::ExternRef(Some(r)) => ffi::WasmEdge_ValueGenExternRef(r.to_guest()),
+                #[cfg(feature = "wasm_ref")]
+                WasmVal::ExternRef(None) => {
+                    ffi::WasmEdge_ValueGenNullRef(ffi::WasmEdge_ValTypeGenExternRef())
+                }
                 WasmVal::UnknownType(v) => v,
             }
         }
@@ -137,6 +497,18 @@ impl Into<ffi::WasmEdge_Value> for &WasmVal {
                 WasmVal::F32(n) => ffi::WasmEdge_ValueGenF32(*n),
                 WasmVal::F64(n) => ffi::WasmEdge_ValueGenF64(*n),
                 WasmVal::V128(n) => ffi::WasmEdge_ValueGenV128(*n),
+                #[cfg(feature = "wasm_ref")]
+                WasmVal::FuncRef(Some(f)) => ffi::WasmEdge_ValueGenFuncRef(f.inner.0),
+                #[cfg(feature = "wasm_ref")]
+                WasmVal::FuncRef(None) => {
+                    ffi::WasmEdge_ValueGenNullRef(ffi::WasmEdge_ValTypeGenFuncRef())
+                }
+                #[cfg(feature = "wasm_ref")]
+                WasmVal::ExternRef(Some(r)) => ffi::WasmEdge_ValueGenExternRef(r.to_guest()),
+                #[cfg(feature = "wasm_ref")]
+                WasmVal::ExternRef(None) => {
+                    ffi::WasmEdge_ValueGenNullRef(ffi::WasmEdge_ValTypeGenExternRef())
+                }
                 WasmVal::UnknownType(v) => v.clone(),
             }
         }
@@ -144,6 +516,11 @@ impl Into<ffi::WasmEdge_Value> for &WasmVal {
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum ValType {
     /// 32-bit integer.
     ///
@@ -170,6 +547,236 @@ pub enum ValType {
     UnsupportedRef,
 }
 
+/// Writes the type as in WAT, e.g. `i32`, which [FromStr](std::str::FromStr) parses back.
+///
+/// The exception is [ValType::UnsupportedRef], written as `unsupportedref`, which isn't parsed:
+/// WasmEdge can't create a value or a function type of it.
+impl std::fmt::Display for ValType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ValType::I32 => "i32",
+            ValType::I64 => "i64",
+            ValType::F32 => "f32",
+            ValType::F64 => "f64",
+            ValType::V128 => "v128",
+            ValType::FuncRef => "funcref",
+            ValType::ExternRef => "externref",
+            ValType::UnsupportedRef => "unsupportedref",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for ValType {
+    type Err = ParseTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "i32" => Ok(ValType::I32),
+            "i64" => Ok(ValType::I64),
+            "f32" => Ok(ValType::F32),
+            "f64" => Ok(ValType::F64),
+            "v128" => Ok(ValType::V128),
+            "funcref" => Ok(ValType::FuncRef),
+            "externref" => Ok(ValType::ExternRef),
+            "unsupportedref" => Err(ParseTypeError::new(
+                s,
+                "`unsupportedref` is only reported by WasmEdge and can't be declared",
+            )),
+            other => Err(ParseTypeError::new(
+                s,
+                format!("unknown value type `{other}`"),
+            )),
+        }
+    }
+}
+
+/// Formats a function type as `(i32, i32) -> i32`.
+///
+/// A single result is written bare, and no result or several results in parentheses.
+pub(crate) fn fmt_func_type(
+    f: &mut std::fmt::Formatter<'_>,
+    params: &[ValType],
+    returns: &[ValType],
+) -> std::fmt::Result {
+    fn fmt_list(f: &mut std::fmt::Formatter<'_>, tys: &[ValType]) -> std::fmt::Result {
+        write!(f, "(")?;
+        for (idx, ty) in tys.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{ty}")?;
+        }
+        write!(f, ")")
+    }
+
+    fmt_list(f, params)?;
+    write!(f, " -> ")?;
+    match returns {
+        [ty] => write!(f, "{ty}"),
+        _ => fmt_list(f, returns),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypeToken<'a> {
+    Open,
+    Close,
+    Comma,
+    Arrow,
+    Word(&'a str),
+    // A WAT identifier such as `$len`, naming a parameter.
+    Id(&'a str),
+}
+
+fn tokenize_func_type(s: &str) -> Result<Vec<TypeToken<'_>>, String> {
+    let mut tokens = vec![];
+    let mut chars = s.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '(' => tokens.push(TypeToken::Open),
+            ')' => tokens.push(TypeToken::Close),
+            ',' => tokens.push(TypeToken::Comma),
+            '-' if matches!(chars.peek(), Some((_, '>'))) => {
+                chars.next();
+                tokens.push(TypeToken::Arrow);
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut end = idx + c.len_utf8();
+                while let Some((next_idx, next)) = chars.peek() {
+                    if next.is_ascii_alphanumeric() || *next == '_' {
+                        end = next_idx + next.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(TypeToken::Word(&s[idx..end]));
+            }
+            '$' => {
+                let mut end = idx + 1;
+                while let Some((next_idx, next)) = chars.peek() {
+                    if next.is_ascii_alphanumeric() || matches!(next, '_' | '.' | '-') {
+                        end = next_idx + next.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if end == idx + 1 {
+                    return Err("expected an identifier after `$`".to_string());
+                }
+                tokens.push(TypeToken::Id(&s[idx..end]));
+            }
+            c => return Err(format!("unexpected character `{c}`")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses a function type written as `(i32, i32) -> i32` or in the WAT style as
+/// `(param i32 i32) (result i32)`, where a parameter can be named as in `(param $len i32)`.
+pub fn parse_func_type(s: &str) -> Result<(Vec<ValType>, Vec<ValType>), ParseTypeError> {
+    let tokens = tokenize_func_type(s).map_err(|reason| ParseTypeError::new(s, reason))?;
+    let parsed = if tokens.contains(&TypeToken::Arrow) {
+        parse_arrow_func_type(&tokens)
+    } else {
+        parse_wat_func_type(&tokens)
+    };
+    parsed.map_err(|reason| ParseTypeError::new(s, reason))
+}
+
+fn parse_val_type(word: &str) -> Result<ValType, String> {
+    word.parse::<ValType>().map_err(|e| e.reason)
+}
+
+// Parses `(t, t, ...)` from the start of `tokens`, returning the types and the rest.
+fn parse_type_list<'a, 'b>(
+    tokens: &'b [TypeToken<'a>],
+) -> Result<(Vec<ValType>, &'b [TypeToken<'a>]), String> {
+    let mut rest = match tokens.split_first() {
+        Some((TypeToken::Open, rest)) => rest,
+        _ => return Err("expected `(`".to_string()),
+    };
+    let mut tys = vec![];
+    loop {
+        match rest.split_first() {
+            Some((TypeToken::Close, after)) if tys.is_empty() => return Ok((tys, after)),
+            Some((TypeToken::Word(word), after)) => {
+                tys.push(parse_val_type(word)?);
+                rest = after;
+            }
+            _ => return Err("expected a value type".to_string()),
+        }
+        match rest.split_first() {
+            Some((TypeToken::Comma, after)) => rest = after,
+            Some((TypeToken::Close, after)) => return Ok((tys, after)),
+            _ => return Err("expected `,` or `)`".to_string()),
+        }
+    }
+}
+
+fn parse_arrow_func_type(tokens: &[TypeToken]) -> Result<(Vec<ValType>, Vec<ValType>), String> {
+    let (params, rest) = parse_type_list(tokens)?;
+    let rest = match rest.split_first() {
+        Some((TypeToken::Arrow, rest)) => rest,
+        _ => return Err("expected `->` after the parameters".to_string()),
+    };
+    let (returns, rest) = match rest.split_first() {
+        Some((TypeToken::Word(word), rest)) => (vec![parse_val_type(word)?], rest),
+        _ => parse_type_list(rest)?,
+    };
+    if !rest.is_empty() {
+        return Err("unexpected tokens after the results".to_string());
+    }
+    Ok((params, returns))
+}
+
+fn parse_wat_func_type(tokens: &[TypeToken]) -> Result<(Vec<ValType>, Vec<ValType>), String> {
+    let mut params = vec![];
+    let mut returns = vec![];
+    let mut rest = tokens;
+    while !rest.is_empty() {
+        let (is_param, after) = match rest {
+            [TypeToken::Open, TypeToken::Word("param"), after @ ..] => (true, after),
+            [TypeToken::Open, TypeToken::Word("result"), after @ ..] => (false, after),
+            _ => return Err("expected `(param ...)` or `(result ...)`".to_string()),
+        };
+        if is_param && !returns.is_empty() {
+            return Err("`param` must come before `result`".to_string());
+        }
+        rest = after;
+        if let [TypeToken::Id(id), after @ ..] = rest {
+            match after {
+                [TypeToken::Word(_), TypeToken::Close, ..] if is_param => {}
+                _ if !is_param => return Err(format!("a result can't be named `{id}`")),
+                _ => return Err(format!("the parameter `{id}` must have exactly one type")),
+            }
+            rest = after;
+        }
+        loop {
+            match rest.split_first() {
+                Some((TypeToken::Word(word), after)) => {
+                    let ty = parse_val_type(word)?;
+                    if is_param {
+                        params.push(ty);
+                    } else {
+                        returns.push(ty);
+                    }
+                    rest = after;
+                }
+                Some((TypeToken::Close, after)) => {
+                    rest = after;
+                    break;
+                }
+                _ => return Err("expected a value type or `)`".to_string()),
+            }
+        }
+    }
+    Ok((params, returns))
+}
+
 impl From<ffi::WasmEdge_ValType> for ValType {
     fn from(value: ffi::WasmEdge_ValType) -> Self {
         unsafe {
@@ -223,3 +830,107 @@ impl From<ValType> for ffi::WasmEdge_ValType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ValType::{ExternRef, FuncRef, F32, F64, I32, I64, V128};
+    use super::*;
+
+    fn parse(s: &str) -> (Vec<ValType>, Vec<ValType>) {
+        parse_func_type(s).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn reason(s: &str) -> String {
+        match parse_func_type(s) {
+            Ok(ty) => panic!("`{s}` parsed as {ty:?}"),
+            Err(e) => e.reason,
+        }
+    }
+
+    struct Signature<'a>(&'a [ValType], &'a [ValType]);
+
+    impl std::fmt::Display for Signature<'_> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            fmt_func_type(f, self.0, self.1)
+        }
+    }
+
+    #[test]
+    fn parses_arrow_func_types() {
+        assert_eq!(parse("(i32, i64) -> f32"), (vec![I32, I64], vec![F32]));
+        assert_eq!(parse("() -> ()"), (vec![], vec![]));
+        assert_eq!(parse("(v128) -> (i32, i32)"), (vec![V128], vec![I32, I32]));
+        assert_eq!(
+            parse(" ( funcref ,externref )->(f64) "),
+            (vec![FuncRef, ExternRef], vec![F64])
+        );
+    }
+
+    #[test]
+    fn parses_wat_func_types() {
+        assert_eq!(
+            parse("(param i32 i64) (result f32)"),
+            (vec![I32, I64], vec![F32])
+        );
+        assert_eq!(
+            parse("(param $ptr i32) (param $len i32) (result i32)"),
+            (vec![I32, I32], vec![I32])
+        );
+        assert_eq!(parse("(param $a.b-c_1 f64)"), (vec![F64], vec![]));
+        assert_eq!(parse("(result i32 i64)"), (vec![], vec![I32, I64]));
+        assert_eq!(parse("(param) (result)"), (vec![], vec![]));
+        assert_eq!(parse(""), (vec![], vec![]));
+    }
+
+    #[test]
+    fn rejects_malformed_arrow_func_types() {
+        assert_eq!(reason("(i32,) -> i32"), "expected a value type");
+        assert_eq!(reason("(i32 -> i32"), "expected `,` or `)`");
+        assert_eq!(reason("i32 -> i32"), "expected `(`");
+        assert_eq!(
+            reason("(i32) -> i32 i32"),
+            "unexpected tokens after the results"
+        );
+        assert_eq!(reason("(i33) -> ()"), "unknown value type `i33`");
+        assert_eq!(reason("(i32) - i32"), "unexpected character `-`");
+    }
+
+    #[test]
+    fn rejects_malformed_wat_func_types() {
+        assert_eq!(
+            reason("(i32) (result i32)"),
+            "expected `(param ...)` or `(result ...)`"
+        );
+        assert_eq!(
+            reason("(result i32) (param i32)"),
+            "`param` must come before `result`"
+        );
+        assert_eq!(
+            reason("(param $x i32 i64)"),
+            "the parameter `$x` must have exactly one type"
+        );
+        assert_eq!(reason("(result $r i32)"), "a result can't be named `$r`");
+        assert_eq!(reason("(param $ i32)"), "expected an identifier after `$`");
+        assert_eq!(reason("(param i32"), "expected a value type or `)`");
+        assert_eq!(reason("(param i32]"), "unexpected character `]`");
+    }
+
+    #[test]
+    fn parses_what_it_prints() {
+        for ty in [I32, I64, F32, F64, V128, FuncRef, ExternRef] {
+            assert_eq!(ty.to_string().parse::<ValType>(), Ok(ty));
+        }
+        assert!("unsupportedref".parse::<ValType>().is_err());
+
+        let signatures: [(&[ValType], &[ValType]); 4] = [
+            (&[], &[]),
+            (&[I32, F64], &[I64]),
+            (&[V128], &[I32, ExternRef]),
+            (&[FuncRef], &[]),
+        ];
+        for (params, returns) in signatures {
+            let text = Signature(params, returns).to_string();
+            assert_eq!(parse(&text), (params.to_vec(), returns.to_vec()), "{text}");
+        }
+    }
+}
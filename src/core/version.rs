@@ -0,0 +1,87 @@
+//! Defines RuntimeVersion, the version of the loaded WasmEdge library.
+
+use std::fmt;
+use std::str::FromStr;
+
+use wasmedge_sys::ffi;
+
+use crate::error::ParseVersionError;
+
+/// A version of WasmEdge, e.g. `0.14.1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RuntimeVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl RuntimeVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Returns the version of the WasmEdge library this process runs with, which may differ from
+    /// the version the SDK was compiled against.
+    pub fn current() -> Self {
+        unsafe {
+            Self {
+                major: ffi::WasmEdge_VersionGetMajor(),
+                minor: ffi::WasmEdge_VersionGetMinor(),
+                patch: ffi::WasmEdge_VersionGetPatch(),
+            }
+        }
+    }
+
+    /// Returns the version of the WasmEdge headers the SDK was compiled against.
+    pub const fn headers() -> Self {
+        Self::new(
+            ffi::WASMEDGE_VERSION_MAJOR,
+            ffi::WASMEDGE_VERSION_MINOR,
+            ffi::WASMEDGE_VERSION_PATCH,
+        )
+    }
+
+    /// Returns the plugin API version of this runtime, which WasmEdge doesn't expose.
+    ///
+    /// It is only known for the release series of the headers, e.g. any 0.14 runtime for 0.14.1
+    /// headers, whose API version is `WasmEdge_Plugin_CurrentAPIVersion`. `None` is returned for
+    /// any other runtime.
+    pub fn plugin_api_version(&self) -> Option<u32> {
+        let headers = Self::headers();
+        ((self.major, self.minor) == (headers.major, headers.minor))
+            .then_some(ffi::WasmEdge_Plugin_CurrentAPIVersion)
+    }
+}
+
+impl fmt::Display for RuntimeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for RuntimeVersion {
+    type Err = ParseVersionError;
+
+    /// Parses `major.minor.patch`, where a missing part is 0 and a pre-release suffix such as
+    /// `-rc.1` is ignored.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let core = s.trim().trim_start_matches('v');
+        let core = core.split(['-', '+']).next().unwrap_or_default();
+        let mut parts = [0u32; 3];
+        let mut count = 0;
+        for part in core.split('.') {
+            if count == parts.len() {
+                return Err(ParseVersionError::new(s, "too many version parts"));
+            }
+            parts[count] = part
+                .parse()
+                .map_err(|_| ParseVersionError::new(s, format!("invalid version part `{part}`")))?;
+            count += 1;
+        }
+        Ok(Self::new(parts[0], parts[1], parts[2]))
+    }
+}
@@ -1,5 +1,13 @@
 use thiserror::Error;
 
+#[cfg(feature = "derive")]
+pub use wasmedge_plugin_sdk_derive::HostError;
+
+use crate::core::types::{ValType, WasmVal};
+use crate::core::version::RuntimeVersion;
+#[cfg(feature = "config")]
+use crate::sdk::plugin_config::ConfigSource;
+
 #[derive(Error, Clone, Debug, PartialEq, Eq)]
 pub enum CoreError {
     #[error("{0}")]
@@ -12,6 +20,10 @@ pub enum CoreError {
     Instantiation(CoreInstantiationError),
     #[error("{0}")]
     Execution(CoreExecutionError),
+    #[error("{0}")]
+    Exit(GuestExit),
+    #[error("{0}")]
+    Host(HostFailure),
     #[error("User error: {0}")]
     User(u32),
     #[error("Asyncify error")]
@@ -29,6 +41,24 @@ impl CoreError {
         CoreError::Common(CoreCommonError::Terminated)
     }
 
+    /// Terminates the guest with an exit code, like WASI `proc_exit`, and a reason for the logs.
+    pub fn exit(code: u32, reason: impl Into<String>) -> Self {
+        CoreError::Exit(GuestExit {
+            code,
+            reason: reason.into(),
+        })
+    }
+
+    /// Fails a host function with a user error code and a message, which the guest can fetch
+    /// with the `last_error` function of the module, see
+    /// [LAST_ERROR_FUNC_NAME](crate::module::LAST_ERROR_FUNC_NAME).
+    pub fn host(code: u32, message: impl Into<String>) -> Self {
+        CoreError::Host(HostFailure {
+            code,
+            message: message.into(),
+        })
+    }
+
     pub fn is_yield(&self) -> bool {
         if let Self::Yield = self {
             true
@@ -38,6 +68,94 @@ impl CoreError {
     }
 }
 
+/// The exit status of a guest terminated by a host function.
+///
+/// It reaches the runtime as [CoreCommonError::Terminated], like WASI `proc_exit`.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+#[error("guest exited with code {code}: {reason}")]
+pub struct GuestExit {
+    pub code: u32,
+    pub reason: String,
+}
+
+/// The failure of a host function with a message for the guest.
+///
+/// It reaches the runtime as a user error of `code`, and the message is kept as the last error of
+/// the module instance of the host function.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+#[error("{message}")]
+pub struct HostFailure {
+    pub code: u32,
+    pub message: String,
+}
+
+/// How an error with a [HostErrorCode] reaches the guest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostErrorKind {
+    /// The host function fails with [CoreError::User] of the code.
+    User,
+    /// The host function returns the negated code as a single `i32`.
+    Return,
+}
+
+/// An error enum whose every variant has a stable code, usually implemented with
+/// `#[derive(HostError)]` under the `derive` feature.
+pub trait HostErrorCode: Sized {
+    const KIND: HostErrorKind;
+
+    /// The name and the code of every variant, e.g. for
+    /// [BindingGenerator::errors](crate::bindgen::BindingGenerator::errors).
+    const CODES: &'static [(&'static str, u32)];
+
+    fn code(&self) -> u32;
+
+    /// Decodes a variant without fields from its code.
+    ///
+    /// `None` is returned for an unknown code and for the code of a variant with fields.
+    fn from_code(code: u32) -> Option<Self>;
+
+    /// Returns the `i32` returned to the guest for the error, which is the negated code.
+    ///
+    /// A code above `i32::MAX` can't be negated, so it returns `i32::MIN` instead.
+    fn return_value(&self) -> i32 {
+        i32::try_from(self.code()).map_or(i32::MIN, |code| -code)
+    }
+
+    /// Returns the result of a host function failing with the error, according to
+    /// [Self::KIND].
+    fn into_result(self) -> Result<Vec<WasmVal>, CoreError> {
+        match Self::KIND {
+            HostErrorKind::User => Err(CoreError::User(self.code())),
+            HostErrorKind::Return => Ok(vec![WasmVal::I32(self.return_value())]),
+        }
+    }
+}
+
+/// The error type for converting a [WasmVal](crate::types::WasmVal) of another type.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+#[error("type mismatch: expected {expected}, found {actual}")]
+pub struct ValTypeMismatch {
+    pub expected: ValType,
+    pub actual: ValType,
+}
+
+/// The error type for parsing a value type or a function type from text.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+#[error("invalid type `{input}`: {reason}")]
+pub struct ParseTypeError {
+    pub input: String,
+    pub reason: String,
+}
+
+impl ParseTypeError {
+    pub(crate) fn new(input: &str, reason: impl Into<String>) -> Self {
+        Self {
+            input: input.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
 /// The error type for the common errors from WasmEdge Core.
 #[derive(Error, Clone, Debug, PartialEq, Eq)]
 pub enum CoreCommonError {
@@ -226,6 +344,8 @@ pub enum InstanceError {
     Create,
     #[error("Fail to create ImportModule context")]
     CreateImportModule,
+    #[error("Not found the target module ({0})")]
+    NotFoundModule(String),
     #[error("Not found the target function ({0})")]
     NotFoundFunc(String),
     #[error("Not found the target table ({0})")]
@@ -241,3 +361,153 @@ pub enum InstanceError {
     #[error("Found an interior nul byte")]
     FoundNulByte(#[from] std::ffi::NulError),
 }
+
+/// The error types for ResourceTable.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum ResourceError {
+    #[error("No handle left in the resource table")]
+    Exhausted,
+    #[error("Invalid resource handle ({0})")]
+    InvalidHandle(i32),
+}
+
+/// The error types for the program options of a plugin.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum OptionError {
+    #[error("Not found the program option ({0})")]
+    NotFound(String),
+    #[error("The program option ({0}) isn't of the requested type")]
+    TypeMismatch(String),
+    #[error("Invalid value `{value}` of the program option ({name}): {reason}")]
+    Invalid {
+        name: String,
+        value: String,
+        reason: String,
+    },
+}
+
+/// The error types for checking the version of the loading WasmEdge runtime.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum VersionError {
+    #[error("Plugin `{plugin}` doesn't support WasmEdge {runtime}, it supports {supported}")]
+    UnsupportedRuntime {
+        plugin: String,
+        runtime: RuntimeVersion,
+        supported: String,
+    },
+    #[error(
+        "Plugin `{plugin}` doesn't support the plugin API {api} of WasmEdge {runtime}, it \
+         supports {supported}"
+    )]
+    UnsupportedApi {
+        plugin: String,
+        runtime: RuntimeVersion,
+        api: u32,
+        supported: String,
+    },
+    #[error(
+        "Plugin `{plugin}` supports the plugin API {supported}, but the plugin API of WasmEdge \
+         {runtime} isn't known"
+    )]
+    UnknownApi {
+        plugin: String,
+        runtime: RuntimeVersion,
+        supported: String,
+    },
+}
+
+/// The error type for parsing a [RuntimeVersion] from text.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+#[error("invalid version `{input}`: {reason}")]
+pub struct ParseVersionError {
+    pub input: String,
+    pub reason: String,
+}
+
+impl ParseVersionError {
+    pub(crate) fn new(input: &str, reason: impl Into<String>) -> Self {
+        Self {
+            input: input.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// The error types for generating guest bindings.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum BindingError {
+    #[error("Not found the target module ({0})")]
+    NotFound(String),
+    #[error("Hints don't match the function `{func}` of the module `{module}`: {reason}")]
+    Mismatch {
+        module: String,
+        func: String,
+        reason: String,
+    },
+}
+
+/// The error types for lifting and lowering the values of a WIT function.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum WitError {
+    #[error("{0}")]
+    Host(CoreError),
+    #[error("{0}")]
+    Memory(CoreError),
+    #[error("Fail to allocate guest memory with cabi_realloc: {0}")]
+    Realloc(CoreError),
+    #[error("Invalid UTF-8 string")]
+    InvalidUtf8,
+    #[error("Invalid char ({0:#x})")]
+    InvalidChar(u32),
+    #[error("Invalid discriminant ({0})")]
+    InvalidDiscriminant(u32),
+    #[error("Unaligned pointer ({0:#x})")]
+    Unaligned(u32),
+    #[error("Missing a core value")]
+    MissingValue,
+    #[error("Too long list of zero-size items ({0})")]
+    ListTooLong(u32),
+    #[error("Too many flags ({0}), at most 32 are supported")]
+    TooManyFlags(usize),
+    #[error("Type mismatch: expected {0}")]
+    TypeMismatch(String),
+}
+
+/// The error types for validating a plugin descriptor.
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum DescriptorError {
+    #[error("The descriptor is null")]
+    Null,
+    #[error("The {0} is null")]
+    NullField(String),
+    #[error("The {0} isn't valid UTF-8")]
+    InvalidUtf8(String),
+    #[error("{count} {what} are declared but their array is null")]
+    NullArray { what: &'static str, count: u32 },
+    #[error("Duplicate {what} name `{name}`")]
+    Duplicate { what: &'static str, name: String },
+    #[error("Unknown type ({ty}) of option {index}")]
+    UnknownOptionType { index: usize, ty: u32 },
+    #[error("API version {found} differs from version {expected} of the SDK")]
+    ApiVersion { found: u32, expected: u32 },
+}
+
+/// The error types for loading the configuration of a plugin.
+#[cfg(feature = "config")]
+#[derive(Error, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    #[error("Fail to read the config file ({}): {reason}", path.display())]
+    File {
+        path: std::path::PathBuf,
+        reason: String,
+    },
+    #[error("Unknown key `{key}` in the {origin}")]
+    UnknownKey { key: String, origin: ConfigSource },
+    #[error("Invalid value `{value}` of `{key}` from the {origin}: {reason}")]
+    Invalid {
+        key: String,
+        value: String,
+        origin: ConfigSource,
+        reason: String,
+    },
+}
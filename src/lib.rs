@@ -4,7 +4,12 @@ mod sdk;
 
 pub use crate::core::config;
 pub use crate::core::executor;
+pub use crate::core::exports;
+pub use crate::core::instance::function;
+pub use crate::core::instance::global;
 pub use crate::core::instance::memory;
+pub use crate::core::store;
 pub use crate::core::types;
+pub use crate::core::version;
 pub use sdk::*;
 mod utils;
@@ -0,0 +1,851 @@
+//! Generates guest bindings for the modules of a plugin from its [PluginManifest].
+//!
+//! A Rust guest gets a crate with the `extern "C"` imports of every module and safe wrappers,
+//! and a C or C++ guest gets a header with the same imports. Both come from the functions the
+//! host module really exports, so host and guest agree on names and signatures.
+//!
+//! WebAssembly types can't tell a buffer from two integers, so [ParamKind] hints say which
+//! pairs of `i32` parameters are a pointer and a length, and [BindingGenerator::status] marks
+//! functions returning a negative `i32` on error. [BindingGenerator::errors] adds a decoder of
+//! the codes of a [HostErrorCode] enum.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::core::exports::FuncExport;
+use crate::core::types::ValType;
+use crate::error::{BindingError, HostErrorCode};
+use crate::sdk::manifest::PluginManifest;
+
+/// How the guest passes a parameter of a host function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// A single value of the parameter type.
+    Value,
+    /// A `&str`, passed as an `i32` pointer and an `i32` length.
+    Str,
+    /// A `&[u8]`, passed as an `i32` pointer and an `i32` length.
+    Bytes,
+    /// A `&mut [u8]` the host writes into, passed as an `i32` pointer and an `i32` length.
+    BytesMut,
+}
+
+impl ParamKind {
+    fn width(self) -> usize {
+        match self {
+            ParamKind::Value => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FuncHint {
+    params: Option<Vec<ParamKind>>,
+    status: bool,
+}
+
+// An error enum of the host, decoded on the guest side.
+#[derive(Debug, Clone)]
+struct ErrorEnum {
+    name: String,
+    codes: Vec<(String, u32)>,
+}
+
+/// A generated Rust guest crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuestCrate {
+    pub name: String,
+    pub cargo_toml: String,
+    pub lib_rs: String,
+}
+
+impl GuestCrate {
+    /// Writes `Cargo.toml` and `src/lib.rs` into a directory.
+    pub fn write(&self, dir: impl AsRef<Path>) -> std::io::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir.join("src"))?;
+        std::fs::write(dir.join("Cargo.toml"), &self.cargo_toml)?;
+        std::fs::write(dir.join("src").join("lib.rs"), &self.lib_rs)
+    }
+}
+
+/// Generates the guest bindings of a plugin.
+#[derive(Debug, Clone)]
+pub struct BindingGenerator<'a> {
+    manifest: &'a PluginManifest,
+    hints: HashMap<(String, String), FuncHint>,
+    errors: Vec<ErrorEnum>,
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "try", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+// Keywords which can't be raw identifiers, so `_` is appended to them instead.
+const RUST_PATH_KEYWORDS: &[&str] = &["crate", "self", "Self", "super"];
+
+/// Turns a name into an identifier by replacing the characters identifiers can't hold.
+fn ident(name: &str) -> String {
+    let mut s: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if s.is_empty() || s.starts_with(|c: char| c.is_ascii_digit()) {
+        s.insert(0, '_');
+    }
+    s
+}
+
+fn rust_ident(name: &str) -> String {
+    let s = ident(name);
+    if RUST_PATH_KEYWORDS.contains(&s.as_str()) {
+        format!("{s}_")
+    } else if RUST_KEYWORDS.contains(&s.as_str()) {
+        format!("r#{s}")
+    } else {
+        s
+    }
+}
+
+fn rust_type(ty: ValType) -> Option<&'static str> {
+    match ty {
+        ValType::I32 => Some("i32"),
+        ValType::I64 => Some("i64"),
+        ValType::F32 => Some("f32"),
+        ValType::F64 => Some("f64"),
+        // Needs the `simd128` target feature in the guest.
+        ValType::V128 => Some("core::arch::wasm32::v128"),
+        _ => None,
+    }
+}
+
+fn c_type(ty: ValType) -> Option<&'static str> {
+    match ty {
+        ValType::I32 => Some("int32_t"),
+        ValType::I64 => Some("int64_t"),
+        ValType::F32 => Some("float"),
+        ValType::F64 => Some("double"),
+        _ => None,
+    }
+}
+
+// A function the guest can import, with its parameters grouped by kind.
+struct GuestFunc<'f> {
+    func: &'f FuncExport,
+    kinds: Vec<ParamKind>,
+    status: bool,
+}
+
+impl<'a> BindingGenerator<'a> {
+    pub fn new(manifest: &'a PluginManifest) -> Self {
+        Self {
+            manifest,
+            hints: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Sets how the guest passes the parameters of a function. Every parameter is a
+    /// [ParamKind::Value] by default.
+    pub fn params(mut self, module: &str, func: &str, kinds: &[ParamKind]) -> Self {
+        self.hints
+            .entry((module.to_string(), func.to_string()))
+            .or_default()
+            .params = Some(kinds.to_vec());
+        self
+    }
+
+    /// Marks a function returning a single `i32` which is negative on error, so its safe
+    /// wrapper returns `Result<i32, i32>`.
+    pub fn status(mut self, module: &str, func: &str) -> Self {
+        self.hints
+            .entry((module.to_string(), func.to_string()))
+            .or_default()
+            .status = true;
+        self
+    }
+
+    /// Adds an enum named `name` with the variants and codes of `E`, whose `from_code` decodes a
+    /// code and whose `from_return` decodes the negative value returned by a host function.
+    pub fn errors<E: HostErrorCode>(mut self, name: &str) -> Self {
+        self.errors.push(ErrorEnum {
+            name: name.to_string(),
+            codes: E::CODES
+                .iter()
+                .map(|(variant, code)| (variant.to_string(), *code))
+                .collect(),
+        });
+        self
+    }
+
+    fn guest_funcs(&self, module: &str) -> Result<Vec<GuestFunc<'a>>, BindingError> {
+        let module_manifest = self
+            .manifest
+            .modules
+            .iter()
+            .find(|m| m.name == module)
+            .ok_or_else(|| BindingError::NotFound(module.to_string()))?;
+        module_manifest
+            .exports
+            .funcs
+            .iter()
+            .map(|func| {
+                let hint = self
+                    .hints
+                    .get(&(module.to_string(), func.name.clone()))
+                    .cloned()
+                    .unwrap_or_default();
+                let kinds = hint
+                    .params
+                    .unwrap_or_else(|| vec![ParamKind::Value; func.params.len()]);
+                let mismatch = |reason: &str| BindingError::Mismatch {
+                    module: module.to_string(),
+                    func: func.name.clone(),
+                    reason: reason.to_string(),
+                };
+
+                let mut params = func.params.iter();
+                for kind in &kinds {
+                    let taken: Vec<_> = params.by_ref().take(kind.width()).collect();
+                    if taken.len() != kind.width() {
+                        return Err(mismatch("the hints take more parameters than it has"));
+                    }
+                    if *kind != ParamKind::Value && taken.iter().any(|ty| **ty != ValType::I32) {
+                        return Err(mismatch("a buffer must be passed as two i32"));
+                    }
+                }
+                if params.next().is_some() {
+                    return Err(mismatch("the hints take fewer parameters than it has"));
+                }
+                if hint.status && func.returns != [ValType::I32] {
+                    return Err(mismatch("a status must be a single i32 result"));
+                }
+                Ok(GuestFunc {
+                    func,
+                    kinds,
+                    status: hint.status,
+                })
+            })
+            .collect()
+    }
+
+    /// Generates a Rust module per plugin module, with the raw imports in `raw` and a safe
+    /// wrapper of the same name for every function.
+    ///
+    /// # Error
+    ///
+    /// If a hint doesn't match the function it names, then an error is returned.
+    pub fn rust_source(&self) -> Result<String, BindingError> {
+        let mut out = String::new();
+        writeln!(
+            out,
+            "//! Guest bindings of the `{}` plugin {}, generated from its manifest.",
+            self.manifest.name, self.manifest.version
+        )
+        .unwrap();
+        writeln!(out, "#![allow(clippy::all, dead_code)]").unwrap();
+
+        for error in &self.errors {
+            write_rust_errors(&mut out, error);
+        }
+        for module in &self.manifest.modules {
+            let funcs = self.guest_funcs(&module.name)?;
+            writeln!(out).unwrap();
+            if !module.description.is_empty() {
+                writeln!(out, "/// {}", module.description).unwrap();
+            }
+            writeln!(out, "pub mod {} {{", rust_ident(&module.name)).unwrap();
+            writeln!(out, "    pub mod raw {{").unwrap();
+            writeln!(
+                out,
+                "        #[link(wasm_import_module = {:?})]",
+                module.name
+            )
+            .unwrap();
+            writeln!(out, "        #[allow(improper_ctypes)]").unwrap();
+            writeln!(out, "        extern \"C\" {{").unwrap();
+            for guest in &funcs {
+                let func = guest.func;
+                let (Some(params), Some(returns)) = (
+                    func.params
+                        .iter()
+                        .map(|ty| rust_type(*ty))
+                        .collect::<Option<Vec<_>>>(),
+                    func.returns
+                        .iter()
+                        .map(|ty| rust_type(*ty))
+                        .collect::<Option<Vec<_>>>(),
+                ) else {
+                    writeln!(
+                        out,
+                        "            // `{}` is skipped: reference types can't be imported.",
+                        func.name
+                    )
+                    .unwrap();
+                    continue;
+                };
+                if returns.len() > 1 {
+                    writeln!(
+                        out,
+                        "            // `{}` is skipped: multiple results can't be imported.",
+                        func.name
+                    )
+                    .unwrap();
+                    continue;
+                }
+                let args = params
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| format!("p{i}: {ty}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let ret = returns
+                    .first()
+                    .map(|ty| format!(" -> {ty}"))
+                    .unwrap_or_default();
+                writeln!(out, "            #[link_name = {:?}]", func.name).unwrap();
+                writeln!(
+                    out,
+                    "            pub fn {}({args}){ret};",
+                    rust_ident(&func.name)
+                )
+                .unwrap();
+            }
+            writeln!(out, "        }}").unwrap();
+            writeln!(out, "    }}").unwrap();
+
+            for guest in &funcs {
+                write_rust_wrapper(&mut out, guest);
+            }
+            writeln!(out, "}}").unwrap();
+        }
+        Ok(out)
+    }
+
+    /// Generates a guest crate named `crate_name` holding [Self::rust_source].
+    pub fn rust_crate(&self, crate_name: &str) -> Result<GuestCrate, BindingError> {
+        let cargo_toml = format!(
+            "[package]\nname = {:?}\nversion = \"{}.{}.{}\"\nedition = \"2021\"\n\n[dependencies]\n",
+            crate_name,
+            self.manifest.version.major,
+            self.manifest.version.minor,
+            self.manifest.version.patch
+        );
+        Ok(GuestCrate {
+            name: crate_name.to_string(),
+            cargo_toml,
+            lib_rs: self.rust_source()?,
+        })
+    }
+
+    /// Generates a C header declaring every function as `<module>_<function>`, imported with
+    /// the `import_module` and `import_name` attributes of clang.
+    ///
+    /// # Error
+    ///
+    /// If a hint doesn't match the function it names, then an error is returned.
+    pub fn c_header(&self) -> Result<String, BindingError> {
+        let guard = format!("WASMEDGE_PLUGIN_{}_H", ident(&self.manifest.name)).to_uppercase();
+        let mut out = String::new();
+        writeln!(
+            out,
+            "/* Guest bindings of the `{}` plugin {}, generated from its manifest. */",
+            self.manifest.name, self.manifest.version
+        )
+        .unwrap();
+        writeln!(out, "#ifndef {guard}\n#define {guard}\n").unwrap();
+        writeln!(out, "#include <stdint.h>\n").unwrap();
+        writeln!(out, "#ifdef __cplusplus\nextern \"C\" {{\n#endif").unwrap();
+
+        for error in &self.errors {
+            let prefix = upper_snake(&error.name);
+            writeln!(
+                out,
+                "\n/* Error codes of `{}`, returned negated. */",
+                error.name
+            )
+            .unwrap();
+            writeln!(out, "enum {} {{", ident(&error.name)).unwrap();
+            for (variant, code) in &error.codes {
+                writeln!(out, "  {prefix}_{} = {code},", upper_snake(variant)).unwrap();
+            }
+            writeln!(out, "}};").unwrap();
+        }
+
+        for module in &self.manifest.modules {
+            let funcs = self.guest_funcs(&module.name)?;
+            if module.description.is_empty() {
+                writeln!(out, "\n/* {} */", module.name).unwrap();
+            } else {
+                writeln!(out, "\n/* {}: {} */", module.name, module.description).unwrap();
+            }
+            for guest in &funcs {
+                let func = guest.func;
+                let returns = func
+                    .returns
+                    .iter()
+                    .map(|ty| c_type(*ty))
+                    .collect::<Option<Vec<_>>>();
+                let Some(returns) = returns.filter(|r| r.len() <= 1) else {
+                    writeln!(
+                        out,
+                        "/* `{}` is skipped: its type can't be declared in C. */",
+                        func.name
+                    )
+                    .unwrap();
+                    continue;
+                };
+                let mut args = Vec::new();
+                let mut params = func.params.iter();
+                let mut skipped = false;
+                for (i, kind) in guest.kinds.iter().enumerate() {
+                    match kind {
+                        ParamKind::Value => match params.next().and_then(|ty| c_type(*ty)) {
+                            Some(ty) => args.push(format!("{ty} p{i}")),
+                            None => skipped = true,
+                        },
+                        _ => {
+                            params.nth(1);
+                            let ptr = match kind {
+                                ParamKind::Str => "const char *",
+                                ParamKind::Bytes => "const uint8_t *",
+                                _ => "uint8_t *",
+                            };
+                            args.push(format!("{ptr}p{i}, int32_t p{i}_len"));
+                        }
+                    }
+                }
+                if skipped {
+                    writeln!(
+                        out,
+                        "/* `{}` is skipped: its type can't be declared in C. */",
+                        func.name
+                    )
+                    .unwrap();
+                    continue;
+                }
+                let args = if args.is_empty() {
+                    "void".to_string()
+                } else {
+                    args.join(", ")
+                };
+                writeln!(
+                    out,
+                    "__attribute__((import_module({:?}), import_name({:?})))",
+                    module.name, func.name
+                )
+                .unwrap();
+                writeln!(
+                    out,
+                    "{} {}_{}({args});",
+                    returns.first().copied().unwrap_or("void"),
+                    ident(&module.name),
+                    ident(&func.name)
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(
+            out,
+            "\n#ifdef __cplusplus\n}}\n#endif\n\n#endif /* {guard} */"
+        )
+        .unwrap();
+        Ok(out)
+    }
+}
+
+/// Turns a `CamelCase` name into `UPPER_SNAKE_CASE`.
+fn upper_snake(name: &str) -> String {
+    let mut s = String::new();
+    let mut prev_lower = false;
+    for c in ident(name).chars() {
+        if c.is_ascii_uppercase() && prev_lower {
+            s.push('_');
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        s.push(c.to_ascii_uppercase());
+    }
+    s
+}
+
+fn write_rust_errors(out: &mut String, error: &ErrorEnum) {
+    let name = rust_ident(&error.name);
+    writeln!(out).unwrap();
+    writeln!(out, "/// Error codes of the host.").unwrap();
+    writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]").unwrap();
+    writeln!(out, "#[repr(u32)]").unwrap();
+    writeln!(out, "pub enum {name} {{").unwrap();
+    for (variant, code) in &error.codes {
+        writeln!(out, "    {} = {code},", rust_ident(variant)).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "impl {name} {{").unwrap();
+    writeln!(out, "    pub fn from_code(code: u32) -> Option<Self> {{").unwrap();
+    writeln!(out, "        match code {{").unwrap();
+    for (variant, code) in &error.codes {
+        writeln!(
+            out,
+            "            {code} => Some(Self::{}),",
+            rust_ident(variant)
+        )
+        .unwrap();
+    }
+    writeln!(out, "            _ => None,").unwrap();
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "    pub fn from_return(value: i32) -> Option<Self> {{").unwrap();
+    writeln!(
+        out,
+        "        if value < 0 {{ Self::from_code(value.unsigned_abs()) }} else {{ None }}"
+    )
+    .unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+}
+
+fn write_rust_wrapper(out: &mut String, guest: &GuestFunc) {
+    let func = guest.func;
+    let types = func
+        .params
+        .iter()
+        .chain(&func.returns)
+        .map(|ty| rust_type(*ty))
+        .collect::<Option<Vec<_>>>();
+    if types.is_none() || func.returns.len() > 1 {
+        return;
+    }
+
+    let mut params = Vec::new();
+    let mut args = Vec::new();
+    let mut tys = func.params.iter();
+    for (i, kind) in guest.kinds.iter().enumerate() {
+        match kind {
+            ParamKind::Value => {
+                let ty = tys.next().and_then(|ty| rust_type(*ty)).unwrap_or("i32");
+                params.push(format!("p{i}: {ty}"));
+                args.push(format!("p{i}"));
+            }
+            ParamKind::Str | ParamKind::Bytes | ParamKind::BytesMut => {
+                tys.nth(1);
+                let (ty, ptr) = match kind {
+                    ParamKind::Str => ("&str", "as_ptr"),
+                    ParamKind::Bytes => ("&[u8]", "as_ptr"),
+                    _ => ("&mut [u8]", "as_mut_ptr"),
+                };
+                params.push(format!("p{i}: {ty}"));
+                args.push(format!("p{i}.{ptr}() as usize as i32"));
+                args.push(format!("p{i}.len() as i32"));
+            }
+        }
+    }
+
+    let ret = func.returns.first().and_then(|ty| rust_type(*ty));
+    let ret_ty = match (guest.status, ret) {
+        (true, _) => " -> Result<i32, i32>".to_string(),
+        (false, Some(ty)) => format!(" -> {ty}"),
+        (false, None) => String::new(),
+    };
+    let name = rust_ident(&func.name);
+    let call = format!("unsafe {{ raw::{name}({}) }}", args.join(", "));
+
+    writeln!(out).unwrap();
+    writeln!(out, "    /// Calls `{}` of the host.", func.name).unwrap();
+    writeln!(out, "    pub fn {name}({}){ret_ty} {{", params.join(", ")).unwrap();
+    if guest.status {
+        writeln!(out, "        let code = {call};").unwrap();
+        writeln!(
+            out,
+            "        if code < 0 {{ Err(code) }} else {{ Ok(code) }}"
+        )
+        .unwrap();
+    } else {
+        writeln!(out, "        {call}").unwrap();
+    }
+    writeln!(out, "    }}").unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::exports::ExportReport;
+    use crate::error::HostErrorKind;
+    use crate::sdk::manifest::ModuleManifest;
+    use crate::sdk::plugin::PluginVersion;
+
+    enum TextError {
+        NotFound,
+        InvalidUtf8,
+        TooLong,
+    }
+
+    impl HostErrorCode for TextError {
+        const KIND: HostErrorKind = HostErrorKind::Return;
+        const CODES: &'static [(&'static str, u32)] =
+            &[("NotFound", 1), ("InvalidUtf8", 2), ("TooLong", 3)];
+
+        fn code(&self) -> u32 {
+            match self {
+                TextError::NotFound => 1,
+                TextError::InvalidUtf8 => 2,
+                TextError::TooLong => 3,
+            }
+        }
+
+        fn from_code(code: u32) -> Option<Self> {
+            match code {
+                1 => Some(TextError::NotFound),
+                2 => Some(TextError::InvalidUtf8),
+                3 => Some(TextError::TooLong),
+                _ => None,
+            }
+        }
+    }
+
+    fn func(name: &str, params: &[ValType], returns: &[ValType]) -> FuncExport {
+        FuncExport {
+            name: name.to_string(),
+            params: params.to_vec(),
+            returns: returns.to_vec(),
+        }
+    }
+
+    fn module(name: &str, description: &str, funcs: Vec<FuncExport>) -> ModuleManifest {
+        ModuleManifest {
+            name: name.to_string(),
+            description: description.to_string(),
+            exports: ExportReport {
+                funcs,
+                ..ExportReport::default()
+            },
+        }
+    }
+
+    fn manifest() -> PluginManifest {
+        use ValType::{FuncRef, F32, F64, I32, I64};
+        PluginManifest {
+            name: "text-tools".to_string(),
+            description: "text helpers".to_string(),
+            version: PluginVersion {
+                major: 1,
+                minor: 2,
+                patch: 3,
+                build: 0,
+            },
+            api_version: 3,
+            modules: vec![
+                module(
+                    "text",
+                    "Text helpers",
+                    vec![
+                        func("fill", &[I32, I32, I32], &[]),
+                        func("hash", &[I32, I32], &[I64]),
+                        func("match", &[I32, I32], &[I32]),
+                        func("pair", &[], &[I32, I32]),
+                        func("pick", &[FuncRef], &[]),
+                        func("scale", &[F32, F64], &[F64]),
+                        func("self", &[], &[I64]),
+                    ],
+                ),
+                module("type", "", vec![func("version", &[], &[I32])]),
+            ],
+            options: vec![],
+        }
+    }
+
+    fn generator(manifest: &PluginManifest) -> BindingGenerator<'_> {
+        BindingGenerator::new(manifest)
+            .params("text", "fill", &[ParamKind::BytesMut, ParamKind::Value])
+            .params("text", "hash", &[ParamKind::Bytes])
+            .params("text", "match", &[ParamKind::Str])
+            .status("text", "match")
+            .errors::<TextError>("TextError")
+    }
+
+    const RUST_SOURCE: &str = r##"//! Guest bindings of the `text-tools` plugin 1.2.3.0, generated from its manifest.
+#![allow(clippy::all, dead_code)]
+
+/// Error codes of the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum TextError {
+    NotFound = 1,
+    InvalidUtf8 = 2,
+    TooLong = 3,
+}
+
+impl TextError {
+    pub fn from_code(code: u32) -> Option<Self> {
+        match code {
+            1 => Some(Self::NotFound),
+            2 => Some(Self::InvalidUtf8),
+            3 => Some(Self::TooLong),
+            _ => None,
+        }
+    }
+
+    pub fn from_return(value: i32) -> Option<Self> {
+        if value < 0 { Self::from_code(value.unsigned_abs()) } else { None }
+    }
+}
+
+/// Text helpers
+pub mod text {
+    pub mod raw {
+        #[link(wasm_import_module = "text")]
+        #[allow(improper_ctypes)]
+        extern "C" {
+            #[link_name = "fill"]
+            pub fn fill(p0: i32, p1: i32, p2: i32);
+            #[link_name = "hash"]
+            pub fn hash(p0: i32, p1: i32) -> i64;
+            #[link_name = "match"]
+            pub fn r#match(p0: i32, p1: i32) -> i32;
+            // `pair` is skipped: multiple results can't be imported.
+            // `pick` is skipped: reference types can't be imported.
+            #[link_name = "scale"]
+            pub fn scale(p0: f32, p1: f64) -> f64;
+            #[link_name = "self"]
+            pub fn self_() -> i64;
+        }
+    }
+
+    /// Calls `fill` of the host.
+    pub fn fill(p0: &mut [u8], p1: i32) {
+        unsafe { raw::fill(p0.as_mut_ptr() as usize as i32, p0.len() as i32, p1) }
+    }
+
+    /// Calls `hash` of the host.
+    pub fn hash(p0: &[u8]) -> i64 {
+        unsafe { raw::hash(p0.as_ptr() as usize as i32, p0.len() as i32) }
+    }
+
+    /// Calls `match` of the host.
+    pub fn r#match(p0: &str) -> Result<i32, i32> {
+        let code = unsafe { raw::r#match(p0.as_ptr() as usize as i32, p0.len() as i32) };
+        if code < 0 { Err(code) } else { Ok(code) }
+    }
+
+    /// Calls `scale` of the host.
+    pub fn scale(p0: f32, p1: f64) -> f64 {
+        unsafe { raw::scale(p0, p1) }
+    }
+
+    /// Calls `self` of the host.
+    pub fn self_() -> i64 {
+        unsafe { raw::self_() }
+    }
+}
+
+pub mod r#type {
+    pub mod raw {
+        #[link(wasm_import_module = "type")]
+        #[allow(improper_ctypes)]
+        extern "C" {
+            #[link_name = "version"]
+            pub fn version() -> i32;
+        }
+    }
+
+    /// Calls `version` of the host.
+    pub fn version() -> i32 {
+        unsafe { raw::version() }
+    }
+}
+"##;
+
+    const C_HEADER: &str = r##"/* Guest bindings of the `text-tools` plugin 1.2.3.0, generated from its manifest. */
+#ifndef WASMEDGE_PLUGIN_TEXT_TOOLS_H
+#define WASMEDGE_PLUGIN_TEXT_TOOLS_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Error codes of `TextError`, returned negated. */
+enum TextError {
+  TEXT_ERROR_NOT_FOUND = 1,
+  TEXT_ERROR_INVALID_UTF8 = 2,
+  TEXT_ERROR_TOO_LONG = 3,
+};
+
+/* text: Text helpers */
+__attribute__((import_module("text"), import_name("fill")))
+void text_fill(uint8_t *p0, int32_t p0_len, int32_t p1);
+__attribute__((import_module("text"), import_name("hash")))
+int64_t text_hash(const uint8_t *p0, int32_t p0_len);
+__attribute__((import_module("text"), import_name("match")))
+int32_t text_match(const char *p0, int32_t p0_len);
+/* `pair` is skipped: its type can't be declared in C. */
+/* `pick` is skipped: its type can't be declared in C. */
+__attribute__((import_module("text"), import_name("scale")))
+double text_scale(float p0, double p1);
+__attribute__((import_module("text"), import_name("self")))
+int64_t text_self(void);
+
+/* type */
+__attribute__((import_module("type"), import_name("version")))
+int32_t type_version(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* WASMEDGE_PLUGIN_TEXT_TOOLS_H */
+"##;
+
+    #[test]
+    fn generates_rust_source() {
+        let manifest = manifest();
+        assert_eq!(generator(&manifest).rust_source().unwrap(), RUST_SOURCE);
+    }
+
+    #[test]
+    fn generates_rust_crate() {
+        let manifest = manifest();
+        let guest = generator(&manifest).rust_crate("text-tools-guest").unwrap();
+        assert_eq!(guest.name, "text-tools-guest");
+        assert_eq!(
+            guest.cargo_toml,
+            "[package]\nname = \"text-tools-guest\"\nversion = \"1.2.3\"\nedition = \"2021\"\n\n\
+             [dependencies]\n"
+        );
+        assert_eq!(guest.lib_rs, RUST_SOURCE);
+    }
+
+    #[test]
+    fn generates_c_header() {
+        let manifest = manifest();
+        assert_eq!(generator(&manifest).c_header().unwrap(), C_HEADER);
+    }
+
+    #[test]
+    fn rejects_hints_not_matching_the_function() {
+        let manifest = manifest();
+        let mismatch = |func: &str, reason: &str| BindingError::Mismatch {
+            module: "text".to_string(),
+            func: func.to_string(),
+            reason: reason.to_string(),
+        };
+        let generator = BindingGenerator::new(&manifest).params("text", "scale", &[ParamKind::Str]);
+        assert_eq!(
+            generator.rust_source(),
+            Err(mismatch("scale", "a buffer must be passed as two i32"))
+        );
+        let generator = BindingGenerator::new(&manifest).status("text", "fill");
+        assert_eq!(
+            generator.c_header(),
+            Err(mismatch("fill", "a status must be a single i32 result"))
+        );
+    }
+}
@@ -0,0 +1,499 @@
+//! Defines PluginManifest, a machine-readable description of a plugin.
+//!
+//! A manifest is read from a plugin descriptor, so it describes what WasmEdge sees, and the
+//! options are listed with their defaults. [PluginManifest::read_descriptor] only reads the
+//! descriptor. The exports of the modules are only known once the modules are created, so
+//! [PluginManifest::from_descriptor] runs the code of the plugin to list them.
+
+use std::ffi::CStr;
+
+use wasmedge_sys::ffi;
+
+use crate::core::exports::ExportReport;
+use crate::core::module::AsInstance;
+use crate::core::store::ModuleRef;
+use crate::error::{DescriptorError, InstanceError};
+use crate::sdk::plugin::{option_text, PluginVersion};
+
+/// The description of a plugin, its modules and its program options.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PluginManifest {
+    pub name: String,
+    pub description: String,
+    pub version: PluginVersion,
+    pub api_version: u32,
+    pub modules: Vec<ModuleManifest>,
+    pub options: Vec<OptionManifest>,
+}
+
+/// A module of a plugin and every export of it with its type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleManifest {
+    pub name: String,
+    pub description: String,
+    pub exports: ExportReport,
+}
+
+/// A program option of a plugin, its type and its default value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptionManifest {
+    pub name: String,
+    pub description: String,
+    /// The Rust name of the type, or `string` for an [OptionString](crate::sdk::plugin::OptionString).
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub ty: String,
+    pub default: Option<String>,
+}
+
+unsafe fn c_str(s: *const ::std::os::raw::c_char) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(s).to_string_lossy().into_owned()
+    }
+}
+
+fn option_type_name(ty: ffi::WasmEdge_ProgramOptionType) -> &'static str {
+    match ty {
+        ffi::WasmEdge_ProgramOptionType_Toggle => "bool",
+        ffi::WasmEdge_ProgramOptionType_Int8 => "i8",
+        ffi::WasmEdge_ProgramOptionType_Int16 => "i16",
+        ffi::WasmEdge_ProgramOptionType_Int32 => "i32",
+        ffi::WasmEdge_ProgramOptionType_Int64 => "i64",
+        ffi::WasmEdge_ProgramOptionType_UInt8 => "u8",
+        ffi::WasmEdge_ProgramOptionType_UInt16 => "u16",
+        ffi::WasmEdge_ProgramOptionType_UInt32 => "u32",
+        ffi::WasmEdge_ProgramOptionType_UInt64 => "u64",
+        ffi::WasmEdge_ProgramOptionType_Float => "f32",
+        ffi::WasmEdge_ProgramOptionType_Double => "f64",
+        ffi::WasmEdge_ProgramOptionType_String => "string",
+        _ => "none",
+    }
+}
+
+unsafe fn module_descs(desc: &ffi::WasmEdge_PluginDescriptor) -> &[ffi::WasmEdge_ModuleDescriptor] {
+    if desc.ModuleDescriptions.is_null() {
+        &[]
+    } else {
+        std::slice::from_raw_parts(desc.ModuleDescriptions, desc.ModuleCount as usize)
+    }
+}
+
+unsafe fn option_descs(desc: &ffi::WasmEdge_PluginDescriptor) -> &[ffi::WasmEdge_ProgramOption] {
+    if desc.ProgramOptions.is_null() {
+        &[]
+    } else {
+        std::slice::from_raw_parts(desc.ProgramOptions, desc.ProgramOptionCount as usize)
+    }
+}
+
+/// Checks a plugin descriptor for what would make WasmEdge crash or misbehave when it loads the
+/// plugin, and returns every problem found.
+///
+/// # Safety
+///
+/// `desc` must be null or point to a plugin descriptor, whose non-null pointers are valid.
+pub unsafe fn validate_descriptor(
+    desc: *const ffi::WasmEdge_PluginDescriptor,
+) -> Vec<DescriptorError> {
+    let Some(desc) = desc.as_ref() else {
+        return vec![DescriptorError::Null];
+    };
+    let mut errors = Vec::new();
+    let mut check_str = |s: *const ::std::os::raw::c_char, what: String| -> Option<String> {
+        if s.is_null() {
+            errors.push(DescriptorError::NullField(what));
+            None
+        } else if let Ok(s) = CStr::from_ptr(s).to_str() {
+            Some(s.to_string())
+        } else {
+            errors.push(DescriptorError::InvalidUtf8(what));
+            None
+        }
+    };
+
+    check_str(desc.Name, "plugin name".to_string());
+    check_str(desc.Description, "plugin description".to_string());
+
+    let mut module_names = Vec::new();
+    for (i, module) in module_descs(desc).iter().enumerate() {
+        if let Some(name) = check_str(module.Name, format!("name of module {i}")) {
+            module_names.push(name);
+        }
+        check_str(module.Description, format!("description of module {i}"));
+    }
+    let mut option_names = Vec::new();
+    for (i, option) in option_descs(desc).iter().enumerate() {
+        if let Some(name) = check_str(option.Name, format!("name of option {i}")) {
+            option_names.push(name);
+        }
+        check_str(option.Description, format!("description of option {i}"));
+    }
+
+    if desc.ModuleCount > 0 && desc.ModuleDescriptions.is_null() {
+        errors.push(DescriptorError::NullArray {
+            what: "modules",
+            count: desc.ModuleCount,
+        });
+    }
+    if desc.ProgramOptionCount > 0 && desc.ProgramOptions.is_null() {
+        errors.push(DescriptorError::NullArray {
+            what: "options",
+            count: desc.ProgramOptionCount,
+        });
+    }
+    for (i, module) in module_descs(desc).iter().enumerate() {
+        if module.Create.is_none() {
+            errors.push(DescriptorError::NullField(format!(
+                "create function of module {i}"
+            )));
+        }
+    }
+    for (i, option) in option_descs(desc).iter().enumerate() {
+        if option_type_name(option.Type) == "none" {
+            errors.push(DescriptorError::UnknownOptionType {
+                index: i,
+                ty: option.Type,
+            });
+        }
+        if option.Storage.is_null() {
+            errors.push(DescriptorError::NullField(format!("storage of option {i}")));
+        }
+        if option.DefaultValue.is_null() {
+            errors.push(DescriptorError::NullField(format!(
+                "default value of option {i}"
+            )));
+        }
+    }
+
+    for (what, names) in [("module", &module_names), ("option", &option_names)] {
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                let error = DescriptorError::Duplicate {
+                    what,
+                    name: name.clone(),
+                };
+                if !errors.contains(&error) {
+                    errors.push(error);
+                }
+            }
+        }
+    }
+
+    if desc.APIVersion != ffi::WasmEdge_Plugin_CurrentAPIVersion {
+        errors.push(DescriptorError::ApiVersion {
+            found: desc.APIVersion,
+            expected: ffi::WasmEdge_Plugin_CurrentAPIVersion,
+        });
+    }
+    errors
+}
+
+impl PluginManifest {
+    /// Reads the manifest of a plugin from its descriptor, with the exports of every module.
+    ///
+    /// # Running plugin code
+    ///
+    /// Every module is created through the descriptor to list its exports and deleted
+    /// afterwards, so the create function of every module and its `on_create` and `on_finalize`
+    /// hooks run once, with whatever side effects they have. Use
+    /// [read_descriptor](Self::read_descriptor) to describe a plugin without running its code.
+    ///
+    /// # Safety
+    ///
+    /// `desc` must point to a valid plugin descriptor, such as the one returned by
+    /// `WasmEdge_Plugin_GetDescriptor`.
+    ///
+    /// # Error
+    ///
+    /// If a module fails to be created, then an error is returned.
+    pub unsafe fn from_descriptor(
+        desc: *const ffi::WasmEdge_PluginDescriptor,
+    ) -> Result<Self, InstanceError> {
+        let mut manifest = Self::read_descriptor(desc).ok_or(InstanceError::Create)?;
+        let desc = &*desc;
+        for (module, module_desc) in manifest.modules.iter_mut().zip(module_descs(desc)) {
+            let create = module_desc
+                .Create
+                .ok_or_else(|| InstanceError::NotFoundModule(module.name.clone()))?;
+            let ctx = create(module_desc);
+            if ctx.is_null() {
+                return Err(InstanceError::Create);
+            }
+            module.exports = ModuleRef::from_raw(ctx).exports();
+            ffi::WasmEdge_ModuleInstanceDelete(ctx);
+        }
+        Ok(manifest)
+    }
+
+    /// Reads the manifest of a plugin from its descriptor without creating its modules, so
+    /// their exports are left empty.
+    ///
+    /// # Safety
+    ///
+    /// `desc` must be null or point to a plugin descriptor, whose non-null pointers are valid.
+    pub unsafe fn read_descriptor(desc: *const ffi::WasmEdge_PluginDescriptor) -> Option<Self> {
+        let desc = desc.as_ref()?;
+        let modules = module_descs(desc)
+            .iter()
+            .map(|module| ModuleManifest {
+                name: c_str(module.Name),
+                description: c_str(module.Description),
+                exports: ExportReport::default(),
+            })
+            .collect();
+        let options = option_descs(desc)
+            .iter()
+            .map(|option| OptionManifest {
+                name: c_str(option.Name),
+                description: c_str(option.Description),
+                ty: option_type_name(option.Type).to_string(),
+                default: option_text(option.Type, option.DefaultValue),
+            })
+            .collect();
+
+        Some(Self {
+            name: c_str(desc.Name),
+            description: c_str(desc.Description),
+            version: desc.Version.into(),
+            api_version: desc.APIVersion,
+            modules,
+            options,
+        })
+    }
+
+    /// Returns the manifest as pretty-printed JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a manifest always serializes")
+    }
+
+    /// Writes the manifest as JSON to a file, e.g. from a small binary or a test of the plugin
+    /// crate, for docs and binding generators to read.
+    #[cfg(feature = "serde")]
+    pub fn write_json(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::raw::c_void;
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::error::CoreError;
+    use crate::memory::Memory;
+    use crate::module::{PluginModule, SyncInstanceRef};
+    use crate::plugin::{ModuleCreateContext, PluginBuilder};
+    use crate::types::WasmVal;
+
+    unsafe extern "C" fn create(
+        _desc: *const ffi::WasmEdge_ModuleDescriptor,
+    ) -> *mut ffi::WasmEdge_ModuleInstanceContext {
+        std::ptr::null_mut()
+    }
+
+    fn module(name: &'static CStr) -> ffi::WasmEdge_ModuleDescriptor {
+        ffi::WasmEdge_ModuleDescriptor {
+            Name: name.as_ptr(),
+            Description: c"a module".as_ptr(),
+            Create: Some(create),
+        }
+    }
+
+    fn option(name: &'static CStr) -> ffi::WasmEdge_ProgramOption {
+        let value = Box::leak(Box::new(0i32)) as *mut i32 as *mut c_void;
+        ffi::WasmEdge_ProgramOption {
+            Name: name.as_ptr(),
+            Description: c"an option".as_ptr(),
+            Type: ffi::WasmEdge_ProgramOptionType_Int32,
+            Storage: value,
+            DefaultValue: value,
+        }
+    }
+
+    fn descriptor(
+        modules: &mut [ffi::WasmEdge_ModuleDescriptor],
+        options: &mut [ffi::WasmEdge_ProgramOption],
+    ) -> ffi::WasmEdge_PluginDescriptor {
+        ffi::WasmEdge_PluginDescriptor {
+            Name: c"plugin".as_ptr(),
+            Description: c"a plugin".as_ptr(),
+            APIVersion: ffi::WasmEdge_Plugin_CurrentAPIVersion,
+            Version: ffi::WasmEdge_PluginVersionData {
+                Major: 0,
+                Minor: 1,
+                Patch: 0,
+                Build: 0,
+            },
+            ModuleCount: modules.len() as u32,
+            ModuleDescriptions: modules.as_mut_ptr(),
+            ProgramOptionCount: options.len() as u32,
+            ProgramOptions: options.as_mut_ptr(),
+        }
+    }
+
+    #[test]
+    fn accepts_a_valid_descriptor() {
+        let mut modules = [module(c"a"), module(c"b")];
+        let mut options = [option(c"x")];
+        let desc = descriptor(&mut modules, &mut options);
+        assert_eq!(unsafe { validate_descriptor(&desc) }, vec![]);
+    }
+
+    #[test]
+    fn rejects_a_null_descriptor() {
+        let errors = unsafe { validate_descriptor(std::ptr::null()) };
+        assert_eq!(errors, vec![DescriptorError::Null]);
+    }
+
+    #[test]
+    fn reports_null_strings() {
+        let mut modules = [module(c"a")];
+        modules[0].Name = std::ptr::null();
+        let mut options = [option(c"x")];
+        options[0].Description = std::ptr::null();
+        let mut desc = descriptor(&mut modules, &mut options);
+        desc.Name = std::ptr::null();
+        assert_eq!(
+            unsafe { validate_descriptor(&desc) },
+            vec![
+                DescriptorError::NullField("plugin name".to_string()),
+                DescriptorError::NullField("name of module 0".to_string()),
+                DescriptorError::NullField("description of option 0".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_counts_without_arrays() {
+        let mut desc = descriptor(&mut [], &mut []);
+        desc.ModuleCount = 2;
+        desc.ModuleDescriptions = std::ptr::null_mut();
+        desc.ProgramOptionCount = 1;
+        desc.ProgramOptions = std::ptr::null_mut();
+        assert_eq!(
+            unsafe { validate_descriptor(&desc) },
+            vec![
+                DescriptorError::NullArray {
+                    what: "modules",
+                    count: 2,
+                },
+                DescriptorError::NullArray {
+                    what: "options",
+                    count: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_duplicate_modules_once() {
+        let mut modules = [module(c"a"), module(c"b"), module(c"a"), module(c"a")];
+        let desc = descriptor(&mut modules, &mut []);
+        assert_eq!(
+            unsafe { validate_descriptor(&desc) },
+            vec![DescriptorError::Duplicate {
+                what: "module",
+                name: "a".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_missing_create_storage_and_api_version() {
+        let mut modules = [module(c"a")];
+        modules[0].Create = None;
+        let mut options = [option(c"x")];
+        options[0].Storage = std::ptr::null_mut();
+        let mut desc = descriptor(&mut modules, &mut options);
+        desc.APIVersion = ffi::WasmEdge_Plugin_CurrentAPIVersion + 1;
+        assert_eq!(
+            unsafe { validate_descriptor(&desc) },
+            vec![
+                DescriptorError::NullField("create function of module 0".to_string()),
+                DescriptorError::NullField("storage of option 0".to_string()),
+                DescriptorError::ApiVersion {
+                    found: ffi::WasmEdge_Plugin_CurrentAPIVersion + 1,
+                    expected: ffi::WasmEdge_Plugin_CurrentAPIVersion,
+                },
+            ]
+        );
+    }
+
+    enum Mode {
+        Fast,
+        Slow,
+    }
+
+    impl FromStr for Mode {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "fast" => Ok(Mode::Fast),
+                "slow" => Ok(Mode::Slow),
+                _ => Err(format!("unknown mode `{s}`")),
+            }
+        }
+    }
+
+    fn run(
+        _inst: &mut SyncInstanceRef,
+        _mem: &mut Memory,
+        _data: &mut (),
+        _args: Vec<WasmVal>,
+    ) -> Result<Vec<WasmVal>, CoreError> {
+        Ok(vec![])
+    }
+
+    fn create_runner(ctx: &ModuleCreateContext) -> *mut ffi::WasmEdge_ModuleInstanceContext {
+        let Ok(mode) = ctx.options().parse::<Mode>("mode") else {
+            return std::ptr::null_mut();
+        };
+        let name = match mode {
+            Mode::Fast => "run_fast",
+            Mode::Slow => "run_slow",
+        };
+        let mut module = PluginModule::create(ctx.module_name(), ()).unwrap();
+        module.add_func(name, "() -> ()", run).unwrap();
+        module.into()
+    }
+
+    fn create_quiet() -> *mut ffi::WasmEdge_ModuleInstanceContext {
+        let mut module = PluginModule::create("quiet", ()).unwrap();
+        module.add_func("run", "() -> ()", run).unwrap();
+        module.without_last_error_func();
+        module.into()
+    }
+
+    #[test]
+    fn reads_the_manifest_of_a_plugin_with_an_enum_option() {
+        let plugin = PluginBuilder::new("modes", "a plugin with an enum option")
+            .module("runner", "runs in a mode", create_runner)
+            .module("quiet", "runs without last_error", create_quiet)
+            .enum_option::<Mode>("mode", "run mode", "slow")
+            .build();
+        let manifest = plugin.manifest().unwrap();
+
+        assert_eq!(
+            manifest.options,
+            vec![OptionManifest {
+                name: "mode".to_string(),
+                description: "run mode".to_string(),
+                ty: "string".to_string(),
+                default: Some("slow".to_string()),
+            }]
+        );
+        let names = |module: usize| {
+            let funcs = &manifest.modules[module].exports.funcs;
+            funcs.iter().map(|f| f.name.as_str()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(0), ["last_error", "run_slow"]);
+        assert_eq!(names(1), ["run"]);
+    }
+}
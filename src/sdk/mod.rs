@@ -1,3 +1,11 @@
+#[cfg(feature = "plugin")]
+pub mod bindgen;
+#[cfg(feature = "plugin")]
+pub mod manifest;
 pub mod module;
 #[cfg(feature = "plugin")]
 pub mod plugin;
+#[cfg(feature = "config")]
+pub mod plugin_config;
+pub mod resource;
+pub mod wit;
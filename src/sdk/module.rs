@@ -1,4 +1,5 @@
 use std::{
+    cell::Cell,
     fmt::Debug,
     ops::{Deref, DerefMut},
 };
@@ -6,16 +7,28 @@ use std::{
 use crate::{
     core::{
         executor::{Executor, InnerExecutor},
-        instance::function::{FnWrapper, Function},
+        instance::function::{FnWrapper, FuncType, Function, IntoFuncSignature},
         instance::memory::Memory,
-        module::{AsInnerInstance, AsInstance, ImportModule, InnerInstance},
+        module::{
+            find_live_instance, last_error, live_instance_names, set_last_error as set_error,
+            AsInnerInstance, ImportModule, InnerInstance,
+        },
+        store::{attached_store, ModuleRef, StoreRef},
         types::{ValType, WasmEdgeString, WasmVal},
     },
-    error::{CoreError, InstanceError},
+    error::{CoreError, CoreExecutionError, InstanceError, ParseTypeError, WitError},
+    sdk::wit::{WitFunc, WitHostFn, WitInterface},
 };
 use thiserror::Error;
 use wasmedge_sys::ffi;
 
+#[cfg(feature = "wasm_ref")]
+use crate::core::{module::guest_objects_of, types::GuestObjectsGuard};
+#[cfg(feature = "plugin")]
+use crate::sdk::plugin::PluginOptions;
+
+pub use crate::core::module::{AsInstance, FinalizeHook, HookResult};
+
 #[derive(Error, Clone, Debug, PartialEq, Eq)]
 pub enum CallError {
     #[error("{0}")]
@@ -32,6 +45,10 @@ pub enum AddFuncError {
     IllegalName,
     #[error("Fail to create Function instance")]
     FunctionCreate,
+    #[error("Not found the WIT function ({0})")]
+    NotFoundWitFunc(String),
+    #[error("{0}")]
+    Signature(#[from] ParseTypeError),
 }
 
 use std::ffi::c_void;
@@ -45,6 +62,150 @@ pub(crate) unsafe extern "C" fn wrapper_sync_fn<T: Sized + Send>(
     returns: *mut ffi::WasmEdge_Value,
     return_len: u32,
 ) -> ffi::WasmEdge_Result {
+    let real_fn: SyncWasmFn<T> = std::mem::transmute(key_ptr);
+    call_host_fn::<T, _>(
+        data_ptr,
+        calling_frame_ctx,
+        params,
+        param_len,
+        returns,
+        return_len,
+        |inst, mem, data, args| real_fn(inst, mem, data, args),
+    )
+}
+
+pub(crate) unsafe extern "C" fn wrapper_dynamic_fn<T: Sized + Send>(
+    key_ptr: *mut c_void,
+    data_ptr: *mut c_void,
+    calling_frame_ctx: *const ffi::WasmEdge_CallingFrameContext,
+    params: *const ffi::WasmEdge_Value,
+    param_len: u32,
+    returns: *mut ffi::WasmEdge_Value,
+    return_len: u32,
+) -> ffi::WasmEdge_Result {
+    let key = &*key_ptr.cast::<DynamicFuncKey<T>>();
+    call_host_fn::<T, _>(
+        data_ptr,
+        calling_frame_ctx,
+        params,
+        param_len,
+        returns,
+        return_len,
+        |inst, mem, data, args| (key.handler)(&key.info, inst, mem, data, args),
+    )
+}
+
+pub(crate) unsafe extern "C" fn wrapper_wit_fn<T: Sized + Send>(
+    key_ptr: *mut c_void,
+    data_ptr: *mut c_void,
+    calling_frame_ctx: *const ffi::WasmEdge_CallingFrameContext,
+    params: *const ffi::WasmEdge_Value,
+    param_len: u32,
+    returns: *mut ffi::WasmEdge_Value,
+    return_len: u32,
+) -> ffi::WasmEdge_Result {
+    let key = &*key_ptr.cast::<WitFuncKey<T>>();
+    call_host_fn::<T, _>(
+        data_ptr,
+        calling_frame_ctx,
+        params,
+        param_len,
+        returns,
+        return_len,
+        |inst, mem, data, args| match key.func.call_host(key.handler, inst, mem, data, args) {
+            Ok(v) => Ok(v),
+            Err(WitError::Host(e)) | Err(WitError::Memory(e)) => Err(e),
+            Err(e) => {
+                log::error!("Fail to call WIT function `{}`: {}", key.func.name, e);
+                Err(CoreError::Execution(CoreExecutionError::HostFuncFailed))
+            }
+        },
+    )
+}
+
+thread_local! {
+    // The host data of the module instance whose host function is running on this thread.
+    static CURRENT_HOST_DATA: Cell<usize> = const { Cell::new(0) };
+}
+
+// Marks the host data of a running host function, and restores the outer one when dropped.
+struct HostDataGuard(usize);
+
+impl HostDataGuard {
+    fn enter(data: usize) -> Self {
+        Self(CURRENT_HOST_DATA.replace(data))
+    }
+}
+
+impl Drop for HostDataGuard {
+    fn drop(&mut self) {
+        CURRENT_HOST_DATA.set(self.0);
+    }
+}
+
+/// Sets the last error message of the module instance whose host function is running, for a host
+/// function which reports a failure to the guest through its return values instead of
+/// [CoreError::host].
+///
+/// It does nothing outside a host function.
+pub fn set_last_error(message: impl Into<String>) {
+    let data = CURRENT_HOST_DATA.get();
+    if data != 0 {
+        set_error(data, message.into());
+    }
+}
+
+/// The name of the function every [PluginModule] exports unless
+/// [without_last_error_func](PluginModule::without_last_error_func) is called, with which guests
+/// fetch the last error message of the module.
+pub const LAST_ERROR_FUNC_NAME: &str = "last_error";
+
+// `last_error(buf_ptr: i32, buf_len: i32) -> i32` copies as much of the last error message as
+// fits into the buffer and returns its full length in bytes, 0 if there is no error, or -1 if
+// the buffer is out of the guest memory.
+fn last_error_fn<T: Sized + Send>(
+    _inst: &mut SyncInstanceRef,
+    mem: &mut Memory,
+    data: &mut T,
+    args: Vec<WasmVal>,
+) -> Result<Vec<WasmVal>, CoreError> {
+    let (buf_ptr, buf_len) = match args[..] {
+        [WasmVal::I32(buf_ptr), WasmVal::I32(buf_len)] => (buf_ptr as u32, buf_len as u32),
+        _ => return Err(CoreError::Execution(CoreExecutionError::FuncTypeMismatch)),
+    };
+    let Some(message) = last_error(data as *mut T as usize) else {
+        return Ok(vec![WasmVal::I32(0)]);
+    };
+    let bytes = message.as_bytes();
+    let len = bytes.len().min(buf_len as usize);
+    if mem.write_bytes(&bytes[..len], buf_ptr).is_err() {
+        return Ok(vec![WasmVal::I32(-1)]);
+    }
+    Ok(vec![WasmVal::I32(bytes.len() as i32)])
+}
+
+unsafe fn call_host_fn<T, F>(
+    data_ptr: *mut c_void,
+    calling_frame_ctx: *const ffi::WasmEdge_CallingFrameContext,
+    params: *const ffi::WasmEdge_Value,
+    param_len: u32,
+    returns: *mut ffi::WasmEdge_Value,
+    return_len: u32,
+    real_fn: F,
+) -> ffi::WasmEdge_Result
+where
+    T: Sized + Send,
+    F: FnOnce(
+        &mut SyncInstanceRef,
+        &mut Memory,
+        &mut T,
+        Vec<WasmVal>,
+    ) -> Result<Vec<WasmVal>, CoreError>,
+{
+    let _current = HostDataGuard::enter(data_ptr as usize);
+    // Every host function is bound with the data of its [ImportModule].
+    #[cfg(feature = "wasm_ref")]
+    let _objects = GuestObjectsGuard::enter(guest_objects_of(data_ptr.cast::<T>()));
     let cous = || -> Result<(), CoreError> {
         let inst_ctx = ffi::WasmEdge_CallingFrameGetModuleInstance(calling_frame_ctx);
         let executor_ctx = ffi::WasmEdge_CallingFrameGetExecutor(calling_frame_ctx);
@@ -63,13 +224,6 @@ pub(crate) unsafe extern "C" fn wrapper_sync_fn<T: Sized + Send>(
         debug_assert!(data_ptr.is_some());
         let data_ptr = data_ptr.unwrap();
 
-        let real_fn: fn(
-            &mut SyncInstanceRef,
-            &mut Memory,
-            &mut T,
-            Vec<WasmVal>,
-        ) -> Result<Vec<WasmVal>, CoreError> = std::mem::transmute(key_ptr);
-
         let input = {
             let raw_input = std::slice::from_raw_parts(params, param_len as usize);
             raw_input
@@ -89,10 +243,22 @@ pub(crate) unsafe extern "C" fn wrapper_sync_fn<T: Sized + Send>(
     };
     match cous() {
         Ok(_) => ffi::WasmEdge_Result { Code: 0x0 },
+        // An exit is requested by the plugin, so it isn't logged as a failure.
+        Err(CoreError::Exit(exit)) => {
+            log::info!("{exit}");
+            CoreError::Exit(exit).into()
+        }
+        Err(CoreError::Host(e)) => {
+            set_error(data_ptr as usize, e.message.clone());
+            CoreError::Host(e).into()
+        }
         Err(e) => e.into(),
     }
 }
 
+/// The name of the module instance of WASI.
+pub const WASI_MODULE_NAME: &str = "wasi_snapshot_preview1";
+
 pub type SyncWasmFn<T> = for<'a> fn(
     &'a mut SyncInstanceRef,
     &'a mut Memory,
@@ -100,6 +266,43 @@ pub type SyncWasmFn<T> = for<'a> fn(
     Vec<WasmVal>,
 ) -> Result<Vec<WasmVal>, CoreError>;
 
+/// The function a dynamic host function was invoked as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DynamicFuncInfo {
+    /// The name the function was registered under.
+    pub name: String,
+    pub params: Vec<ValType>,
+    pub returns: Vec<ValType>,
+}
+
+impl DynamicFuncInfo {
+    /// Returns the type the function was registered with, or `None` if WasmEdge fails to create
+    /// it.
+    pub fn signature(&self) -> Option<FuncType> {
+        FuncType::create(self.params.clone(), self.returns.clone())
+    }
+}
+
+/// A host function which can be registered under many names with different signatures.
+pub type DynamicWasmFn<T> = for<'a> fn(
+    &'a DynamicFuncInfo,
+    &'a mut SyncInstanceRef,
+    &'a mut Memory,
+    &'a mut T,
+    Vec<WasmVal>,
+) -> Result<Vec<WasmVal>, CoreError>;
+
+struct DynamicFuncKey<T> {
+    info: DynamicFuncInfo,
+    handler: DynamicWasmFn<T>,
+}
+
+struct WitFuncKey<T> {
+    func: WitFunc,
+    handler: WitHostFn<T>,
+}
+
 pub struct SyncInstanceRef {
     inst: InnerInstance,
     executor: Executor,
@@ -119,6 +322,81 @@ impl SyncInstanceRef {
         let result = self.executor.run_func_ref(&func, &args)?;
         Ok(result)
     }
+
+    /// Terminates the guest with an exit code and a reason, like WASI `proc_exit`.
+    ///
+    /// The returned error must be returned from the host function, and it reaches the runtime as
+    /// [CoreCommonError::Terminated](crate::error::CoreCommonError::Terminated). The reason is
+    /// logged at the info level rather than as an error.
+    ///
+    /// When the WASI module is [reachable](Self::find_module), e.g. through a store attached by
+    /// the embedder, its `proc_exit` is called first so that the runtime reports the code as the
+    /// exit code of the guest. Under the `wasmedge` CLI it isn't reachable, and the guest exits
+    /// with the status of a terminated guest.
+    pub fn exit(&mut self, code: u32, reason: impl Into<String>) -> CoreError {
+        if let Ok(wasi) = self.find_module(WASI_MODULE_NAME) {
+            if let Ok(proc_exit) = wasi.get_func("proc_exit") {
+                let _ = self
+                    .executor
+                    .run_func_ref(&proc_exit, &[WasmVal::I32(code as i32)]);
+            }
+        }
+        CoreError::exit(code, reason)
+    }
+
+    /// Returns the name of the calling module instance.
+    pub fn module_name(&self) -> Option<String> {
+        unsafe { ModuleRef::from_raw(self.get_mut_ptr()) }.name()
+    }
+
+    /// Returns the store the embedder attached to the executor of the calling frame with
+    /// [attach_store](crate::store::attach_store).
+    ///
+    /// WasmEdge doesn't expose the store through the calling frame, so it is `None` under the
+    /// `wasmedge` CLI.
+    pub fn store(&self) -> Option<StoreRef<'_>> {
+        unsafe { attached_store(self.executor.inner.0) }
+    }
+
+    /// Returns the names of the module instances reachable from the calling frame: the calling
+    /// module, the modules of the attached [store](Self::store) if any, and the live module
+    /// instances created by this plugin.
+    pub fn module_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.module_name().into_iter().collect();
+        let store_names = self.store().and_then(|store| store.module_names());
+        for name in store_names
+            .into_iter()
+            .flatten()
+            .chain(live_instance_names())
+        {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Returns the module instance reachable from the calling frame by name, looking in the
+    /// calling module, the attached [store](Self::store) and the live module instances created by
+    /// this plugin, in this order.
+    ///
+    /// The returned module borrows `self`, so it can't be kept after the host function returns.
+    ///
+    /// # Error
+    ///
+    /// If fail to find the target module instance, then an error is returned.
+    pub fn find_module(&self, name: &str) -> Result<ModuleRef<'_>, InstanceError> {
+        if self.module_name().as_deref() == Some(name) {
+            return Ok(unsafe { ModuleRef::from_raw(self.get_mut_ptr()) });
+        }
+        if let Some(module) = self.store().and_then(|store| store.find_module(name).ok()) {
+            return Ok(module);
+        }
+        match find_live_instance(name) {
+            Some(ctx) => Ok(unsafe { ModuleRef::from_raw(ctx) }),
+            None => Err(InstanceError::NotFoundModule(name.to_string())),
+        }
+    }
 }
 
 pub struct SyncInstance {
@@ -137,12 +415,42 @@ impl SyncInstance {
     }
 }
 
+/// A hook run on the host data when the module instance is handed to WasmEdge.
+#[cfg(feature = "plugin")]
+pub type CreateHook<T> = fn(&mut T, &PluginOptions) -> HookResult;
+
 pub struct PluginModule<T: Send + Sized> {
     pub(crate) inner: ImportModule<T>,
+    // Whether the `last_error` function is added when the module is handed to WasmEdge.
+    last_error: bool,
+    #[cfg(feature = "plugin")]
+    on_create: Option<CreateHook<T>>,
 }
 
 impl<T: Send + Sized> Into<*mut ffi::WasmEdge_ModuleInstanceContext> for PluginModule<T> {
-    fn into(self) -> *mut ffi::WasmEdge_ModuleInstanceContext {
+    fn into(mut self) -> *mut ffi::WasmEdge_ModuleInstanceContext {
+        if self.last_error && self.get_func(LAST_ERROR_FUNC_NAME).is_err() {
+            if let Err(e) = self.add_last_error_func() {
+                log::error!("Fail to add `{LAST_ERROR_FUNC_NAME}`: {e}");
+            }
+        }
+        #[cfg(feature = "plugin")]
+        if let Some(on_create) = self.on_create {
+            let data = unsafe { &mut *self.inner.data_ptr };
+            if let Err(e) = on_create(data, &PluginOptions::current()) {
+                let name = unsafe { ModuleRef::from_raw(self.get_mut_ptr()) }.name();
+                log::error!(
+                    "Fail to create module `{}`: {}",
+                    name.unwrap_or_default(),
+                    e
+                );
+                // The host data was never set up, so only drop it.
+                let mut inner = self.inner;
+                inner.clear_on_finalize();
+                unsafe { ffi::WasmEdge_ModuleInstanceDelete(inner.get_mut_ptr()) };
+                return std::ptr::null_mut();
+            }
+        }
         self.inner.inner.into()
     }
 }
@@ -163,7 +471,48 @@ impl<T: Send + Sized> DerefMut for PluginModule<T> {
 impl<T: Send + Sized> PluginModule<T> {
     pub fn create<S: AsRef<str>>(name: S, data: T) -> Result<Self, InstanceError> {
         let inner = ImportModule::create(name, data)?;
-        Ok(Self { inner })
+        Ok(Self {
+            inner,
+            last_error: true,
+            #[cfg(feature = "plugin")]
+            on_create: None,
+        })
+    }
+
+    /// Adds the [LAST_ERROR_FUNC_NAME] function,
+    /// `last_error(buf_ptr: i32, buf_len: i32) -> i32`, with which the guest fetches the last
+    /// error message of the module instance.
+    ///
+    /// It copies as much of the message as fits into the buffer and returns its full length in
+    /// bytes, 0 if there is no error, or -1 if the buffer is out of the guest memory.
+    ///
+    /// Every module gets it when it is handed to WasmEdge, so this is only needed to have it
+    /// before, e.g. to call it from the host.
+    pub fn add_last_error_func(&mut self) -> Result<(), AddFuncError> {
+        self.add_func(
+            LAST_ERROR_FUNC_NAME,
+            (vec![ValType::I32, ValType::I32], vec![ValType::I32]),
+            last_error_fn::<T>,
+        )
+    }
+
+    /// Leaves out the [LAST_ERROR_FUNC_NAME] function, which is otherwise added when the module
+    /// instance is handed to WasmEdge, e.g. when the guests import a function of that name from
+    /// the module with another meaning.
+    ///
+    /// A function of that name added by the module itself is always kept.
+    pub fn without_last_error_func(&mut self) {
+        self.last_error = false;
+    }
+
+    /// Sets the hook run on the host data when the module instance is handed to WasmEdge.
+    ///
+    /// If the hook returns an error, the error is reported through the log and the module
+    /// instance isn't created. The hook set by [on_finalize](ImportModule::on_finalize) isn't run
+    /// then.
+    #[cfg(feature = "plugin")]
+    pub fn on_create(&mut self, hook: CreateHook<T>) {
+        self.on_create = Some(hook);
     }
 
     pub unsafe fn add_custom_func(
@@ -186,12 +535,17 @@ impl<T: Send + Sized> PluginModule<T> {
         Ok(())
     }
 
+    /// Adds a host function of a type given as a `(params, returns)` tuple, a [FuncType] or text
+    /// such as `"(i32, i32) -> i32"`.
+    ///
+    /// [FuncType]: crate::function::FuncType
     pub fn add_func(
         &mut self,
         name: &str,
-        ty: (Vec<ValType>, Vec<ValType>),
+        ty: impl IntoFuncSignature,
         real_fn: SyncWasmFn<T>,
     ) -> Result<(), AddFuncError> {
+        let ty = ty.into_signature()?;
         unsafe {
             self.add_custom_func(
                 name,
@@ -202,4 +556,66 @@ impl<T: Send + Sized> PluginModule<T> {
             )
         }
     }
+
+    /// Adds a host function whose handler also receives the name and the type it was invoked as,
+    /// so that one handler can serve many functions.
+    pub fn add_dynamic_func(
+        &mut self,
+        name: &str,
+        ty: impl IntoFuncSignature,
+        handler: DynamicWasmFn<T>,
+    ) -> Result<(), AddFuncError>
+    where
+        T: 'static,
+    {
+        let ty = ty.into_signature()?;
+        let key = Box::new(DynamicFuncKey {
+            info: DynamicFuncInfo {
+                name: name.to_string(),
+                params: ty.0.clone(),
+                returns: ty.1.clone(),
+            },
+            handler,
+        });
+        let key_ptr = &*key as *const DynamicFuncKey<T> as *mut c_void;
+        unsafe {
+            self.add_custom_func(
+                name,
+                ty,
+                wrapper_dynamic_fn::<T>,
+                key_ptr,
+                self.inner.data_ptr,
+            )?;
+        }
+        self.inner.keep_func_key(key);
+        Ok(())
+    }
+
+    /// Adds a function of a WIT interface. The guest calls it with the canonical ABI, and the
+    /// handler gets the lifted parameters and returns the result to lower.
+    ///
+    /// Strings and lists returned to the guest are allocated with the `cabi_realloc` export of
+    /// the guest, which wit-bindgen generates.
+    pub fn add_wit_func(
+        &mut self,
+        interface: &WitInterface,
+        name: &str,
+        handler: WitHostFn<T>,
+    ) -> Result<(), AddFuncError>
+    where
+        T: 'static,
+    {
+        let func = interface
+            .func(name)
+            .ok_or_else(|| AddFuncError::NotFoundWitFunc(name.to_string()))?
+            .clone();
+        let ty = func.core_type();
+        let key = Box::new(WitFuncKey { func, handler });
+        let key_ptr = &*key as *const WitFuncKey<T> as *mut c_void;
+        unsafe {
+            self.add_custom_func(name, ty, wrapper_wit_fn::<T>, key_ptr, self.inner.data_ptr)?;
+        }
+        self.inner.keep_func_key(key);
+        Ok(())
+    }
 }
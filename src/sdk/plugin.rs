@@ -1,8 +1,17 @@
+use crate::error::{InstanceError, OptionError, VersionError};
+use crate::sdk::manifest::PluginManifest;
+use crate::version::RuntimeVersion;
 pub use lazy_static::lazy_static;
 pub use paste::paste;
-use std::any::TypeId;
-use std::ffi::CString;
+use std::any::{Any, TypeId};
+use std::ffi::{c_void, CStr, CString, OsString};
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
 use std::slice;
+use std::str::FromStr;
+use std::sync::{Mutex, Once, OnceLock};
 pub use wasmedge_sys::ffi;
 
 #[macro_export]
@@ -12,6 +21,8 @@ macro_rules! register_plugin {
         plugin_description = $plugin_description:expr,
         version = ($major:expr, $minor: expr, $patch: expr, $build: expr),
         modules = [$({$module_name:expr, $module_description:expr, $module_create_fn:ident}),*]
+        $(, on_load = $on_load:expr)?
+        $(, on_unload = $on_unload:expr)?
     ) =>
     {
         wasmedge_plugin_sdk::plugin::register_plugin!(
@@ -20,6 +31,8 @@ macro_rules! register_plugin {
             version = ($major, $minor, $patch, $build),
             modules = [$({$module_name, $module_description, $module_create_fn}),*],
             options = []
+            $(, on_load = $on_load)?
+            $(, on_unload = $on_unload)?
         );
     };
     (
@@ -35,114 +48,69 @@ macro_rules! register_plugin {
                     $option_default:expr
                 }
             ),*]
+        $(, on_load = $on_load:expr)?
+        $(, on_unload = $on_unload:expr)?
     ) =>
     {
         wasmedge_plugin_sdk::plugin::paste! {
-            use std::sync::atomic::{AtomicPtr, Ordering};
-            use wasmedge_plugin_sdk::plugin::ffi::*;
-            use wasmedge_plugin_sdk::plugin::Placeholder;
-
-            // Generate option getter
+            // Generate option getters
             $(
                 pub fn [<$option_name>]() -> *const $option_type {
-                    [<$option_name:upper _STORAGE>].load(Ordering::SeqCst)
+                    wasmedge_plugin_sdk::plugin::Plugin::get_or_init(plugin_builder)
+                        .option_storage::<$option_type>($option_name)
                 }
-            )*
 
-            #[export_name = "WasmEdge_Plugin_GetDescriptor"]
-            pub extern "C" fn plugin_hook() -> *const WasmEdge_PluginDescriptor {
-                PLUGIN_DESCRIPTION.load(Ordering::SeqCst)
-            }
-
-            // Generate module create extern C wrap functions
-            $(
-                unsafe extern "C" fn [<$module_name _ $module_create_fn _wrap>](
-                    _: *const WasmEdge_ModuleDescriptor,
-                ) -> *mut WasmEdge_ModuleInstanceContext {
-                    $module_create_fn().into()
+                pub fn [<$option_name _value>](
+                ) -> <$option_type as wasmedge_plugin_sdk::plugin::OptionValue>::Value {
+                    wasmedge_plugin_sdk::plugin::Plugin::get_or_init(plugin_builder)
+                        .options()
+                        .value::<$option_type>($option_name)
+                        .expect("the option is registered by register_plugin!")
                 }
             )*
 
-            // Generate static options vec
-            static mut [<PROGRAM_OPTION_VEC>]: Vec<WasmEdge_ProgramOption> = Vec::new();
-
-            // Generate static module description vec
-            static mut [<MODULE_DESCRIPTION_VEC>]: Vec<WasmEdge_ModuleDescriptor> = Vec::new();
-
-            wasmedge_plugin_sdk::plugin::lazy_static! {
-                // Generate options storage and default value
-                $(
-                    static ref [<$option_name:upper _DEFAULT>]: AtomicPtr<$option_type> = {
-                        let v = Box::new($option_default);
-                        AtomicPtr::new(Box::into_raw(v))
-                    };
-
-                    static ref [<$option_name:upper _STORAGE>]: AtomicPtr<$option_type> = {
-                        let v = $option_type::create_placeholder();
-                        AtomicPtr::new(Box::into_raw(v))
-                    };
-                )*
+            fn plugin_builder() -> wasmedge_plugin_sdk::plugin::PluginBuilder {
+                wasmedge_plugin_sdk::plugin::PluginBuilder::new($plugin_name, $plugin_description)
+                    .version($major, $minor, $patch, $build)
+                    $(.module($module_name, $module_description, $module_create_fn))*
+                    $(.option::<$option_type>($option_name, $option_description, $option_default))*
+                    $(.on_load($on_load))?
+                    $(.on_unload($on_unload))?
+            }
 
-                // Generate options content
-                static ref [<PROGRAM_OPTIONS>]: AtomicPtr<WasmEdge_ProgramOption> =
-                {
-                    unsafe {
-                        $(
-                            [<PROGRAM_OPTION_VEC>].push(
-                                WasmEdge_ProgramOption {
-                                    Name: concat!($option_name,'\0').as_ptr().cast(),
-                                    Description: concat!($option_description,'\0').as_ptr().cast(),
-                                    Type: wasmedge_plugin_sdk::plugin::select_type::<$option_type>(),
-                                    Storage: [<$option_name:upper _STORAGE>].load(Ordering::SeqCst)
-                                        as *const _ as *mut ::std::os::raw::c_void,
-                                    DefaultValue: [<$option_name:upper _DEFAULT>].load(Ordering::SeqCst)
-                                        as *const _ as *const ::std::os::raw::c_void,
-                                }
-                            );
-                        )*
-                        AtomicPtr::new([<PROGRAM_OPTION_VEC>].as_mut_ptr())
-                    }
-                };
+            wasmedge_plugin_sdk::plugin::export_plugin!(plugin_builder);
+        }
+    };
+}
 
-                // Generate module descriptions content
-                static ref [<MODULE_DESCRIPTIONS>]: AtomicPtr<WasmEdge_ModuleDescriptor> =
-                {
-                    unsafe {
-                        $(
-                            [<MODULE_DESCRIPTION_VEC>].push(
-                                WasmEdge_ModuleDescriptor {
-                                    Name: concat!($module_name,'\0').as_ptr().cast(),
-                                    Description: concat!($module_description,'\0').as_ptr().cast(),
-                                    Create: Some([<$module_name _ $module_create_fn _wrap>]),
-                                }
-                            );
-                        )*
-                        AtomicPtr::new([<MODULE_DESCRIPTION_VEC>].as_mut_ptr())
-                    }
-                };
+/// Exports `WasmEdge_Plugin_GetDescriptor`, which builds the plugin with the given
+/// `fn() -> PluginBuilder` the first time WasmEdge retrieves the descriptor, and checks the
+/// version of the runtime retrieving it.
+///
+/// On Linux, Android and FreeBSD, it also registers [Plugin::unload] to run when the library is
+/// unloaded, through `.fini_array`. On other targets, such as macOS and Windows, nothing calls it
+/// automatically.
+#[macro_export]
+macro_rules! export_plugin {
+    ($builder_fn:expr) => {
+        #[export_name = "WasmEdge_Plugin_GetDescriptor"]
+        pub extern "C" fn plugin_hook(
+        ) -> *const wasmedge_plugin_sdk::plugin::ffi::WasmEdge_PluginDescriptor {
+            wasmedge_plugin_sdk::plugin::Plugin::get_or_init($builder_fn).load_descriptor()
+        }
 
-                // Generate plugin description content
-                static ref PLUGIN_DESCRIPTION: AtomicPtr<WasmEdge_PluginDescriptor> =
-                {
-                    let plugin_description = Box::new(WasmEdge_PluginDescriptor {
-                        Name: concat!($plugin_name,'\0').as_ptr().cast(),
-                        Description: concat!($plugin_description,'\0').as_ptr().cast(),
-                        APIVersion: WasmEdge_Plugin_CurrentAPIVersion,
-                        Version: WasmEdge_PluginVersionData {
-                            Major: $major,
-                            Minor: $minor,
-                            Patch: $patch,
-                            Build: $build,
-                        },
-                        ModuleCount: wasmedge_plugin_sdk::plugin::count(&[$($module_name),*]),
-                        ModuleDescriptions: [<MODULE_DESCRIPTIONS>].load(Ordering::SeqCst),
-                        ProgramOptionCount: wasmedge_plugin_sdk::plugin::count(&[$($option_name),*]),
-                        ProgramOptions: [<PROGRAM_OPTIONS>].load(Ordering::SeqCst),
-                    });
-                    AtomicPtr::new(Box::into_raw(plugin_description))
-                };
+        // Run by the dynamic loader when the library is unloaded or the process exits.
+        #[used]
+        #[cfg_attr(
+            any(target_os = "linux", target_os = "android", target_os = "freebsd"),
+            link_section = ".fini_array"
+        )]
+        static PLUGIN_UNLOAD_HOOK: extern "C" fn() = {
+            extern "C" fn plugin_unload() {
+                wasmedge_plugin_sdk::plugin::Plugin::unload();
             }
-        }
+            plugin_unload
+        };
     };
 }
 
@@ -151,12 +119,29 @@ pub const fn count<const N: usize>(_: &'static [&str; N]) -> u32 {
 }
 
 // Define OptionString like WasmEdgeString but without derive(Copy)
+//
+// WasmEdge points the storage of a string option at the parsed string rather than copying into
+// it, so an OptionString never owns its buffer and has no limit on its length.
 #[repr(C)]
 #[derive(Debug)]
 pub struct OptionString {
     pub length: u32,
     pub buf: *const ::std::os::raw::c_char,
 }
+impl From<&'static str> for OptionString {
+    fn from(s: &'static str) -> Self {
+        OptionString {
+            length: s.len() as u32,
+            buf: s.as_ptr().cast(),
+        }
+    }
+}
+impl From<String> for OptionString {
+    /// Leaks the string, as a program option lives as long as the plugin.
+    fn from(s: String) -> Self {
+        Self::from(&*Box::leak(s.into_boxed_str()))
+    }
+}
 impl From<OptionString> for ffi::WasmEdge_String {
     fn from(source: OptionString) -> ffi::WasmEdge_String {
         ffi::WasmEdge_String {
@@ -166,23 +151,28 @@ impl From<OptionString> for ffi::WasmEdge_String {
     }
 }
 impl OptionString {
-    pub fn to_string(&self) -> Result<String, Box<dyn std::error::Error>> {
-        let buf_slice =
-            unsafe { slice::from_raw_parts(self.buf as *const u8, self.length as usize) };
-        let vec_buf = Vec::from(buf_slice);
-        let c_string = CString::new(vec_buf)?;
-        Ok(c_string.into_string()?)
-    }
-}
-impl Drop for OptionString {
-    fn drop(&mut self) {
-        unsafe {
-            drop(Box::from_raw(self.buf as *mut i8));
+    /// Returns the bytes of the string, up to a trailing nul byte if there is one.
+    pub fn as_bytes(&self) -> &[u8] {
+        if self.buf.is_null() {
+            return &[];
+        }
+        let bytes = unsafe { slice::from_raw_parts(self.buf as *const u8, self.length as usize) };
+        match bytes.iter().position(|b| *b == 0) {
+            Some(len) => &bytes[..len],
+            None => bytes,
         }
     }
+
+    pub fn to_string(&self) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(std::str::from_utf8(self.as_bytes())?.to_string())
+    }
 }
 pub trait Placeholder {
     fn create_placeholder() -> Box<Self>;
+
+    /// Creates the storage of an option holding its default value, which it keeps until WasmEdge
+    /// parses the command line.
+    fn create_from_default(default: &Self) -> Box<Self>;
 }
 impl<T> Placeholder for T
 where
@@ -191,48 +181,969 @@ where
     fn create_placeholder() -> Box<Self> {
         Box::new(T::default())
     }
+
+    fn create_from_default(default: &Self) -> Box<Self> {
+        Box::new(*default)
+    }
 }
 impl Placeholder for OptionString {
     fn create_placeholder() -> Box<Self> {
-        let length: u32 = 128;
-        let buf = vec![0i8; length as usize].into_boxed_slice();
-        let buf_ptr = Box::into_raw(buf) as *const ::std::os::raw::c_char;
         Box::new(OptionString {
-            length,
-            buf: buf_ptr,
+            length: 0,
+            buf: std::ptr::null(),
         })
     }
+
+    // The default never owns its buffer either, so the storage shares it.
+    fn create_from_default(default: &Self) -> Box<Self> {
+        Box::new(OptionString {
+            length: default.length,
+            buf: default.buf,
+        })
+    }
+}
+
+/// A type of program option which can be read safely from its storage.
+pub trait OptionValue: Placeholder + 'static {
+    type Value;
+
+    fn read(storage: &'static Self) -> Self::Value;
+}
+
+macro_rules! impl_option_value {
+    ($($t:ty),*) => {
+        $(impl OptionValue for $t {
+            type Value = &'static $t;
+
+            fn read(storage: &'static Self) -> Self::Value {
+                storage
+            }
+        })*
+    };
+}
+
+impl_option_value!(bool, i8, i16, i32, i64, u8, u16, u32, u64, f32, f64);
+
+impl OptionValue for OptionString {
+    type Value = String;
+
+    fn read(storage: &'static Self) -> Self::Value {
+        String::from_utf8_lossy(storage.as_bytes()).into_owned()
+    }
 }
 
 pub fn select_type<T: 'static>() -> ffi::WasmEdge_ProgramOptionType {
+    try_select_type::<T>().expect("Unsupported option type")
+}
+
+pub(crate) fn try_select_type<T: 'static>() -> Option<ffi::WasmEdge_ProgramOptionType> {
     let type_id = TypeId::of::<T>();
     if type_id == TypeId::of::<bool>() {
-        ffi::WasmEdge_ProgramOptionType_Toggle
+        Some(ffi::WasmEdge_ProgramOptionType_Toggle)
     } else if type_id == TypeId::of::<i8>() {
-        ffi::WasmEdge_ProgramOptionType_Int8
+        Some(ffi::WasmEdge_ProgramOptionType_Int8)
     } else if type_id == TypeId::of::<i16>() {
-        ffi::WasmEdge_ProgramOptionType_Int16
+        Some(ffi::WasmEdge_ProgramOptionType_Int16)
     } else if type_id == TypeId::of::<i32>() {
-        ffi::WasmEdge_ProgramOptionType_Int32
+        Some(ffi::WasmEdge_ProgramOptionType_Int32)
     } else if type_id == TypeId::of::<i64>() {
-        ffi::WasmEdge_ProgramOptionType_Int64
+        Some(ffi::WasmEdge_ProgramOptionType_Int64)
     } else if type_id == TypeId::of::<u8>() {
-        ffi::WasmEdge_ProgramOptionType_UInt8
+        Some(ffi::WasmEdge_ProgramOptionType_UInt8)
     } else if type_id == TypeId::of::<u16>() {
-        ffi::WasmEdge_ProgramOptionType_UInt16
+        Some(ffi::WasmEdge_ProgramOptionType_UInt16)
     } else if type_id == TypeId::of::<u32>() {
-        ffi::WasmEdge_ProgramOptionType_UInt32
+        Some(ffi::WasmEdge_ProgramOptionType_UInt32)
     } else if type_id == TypeId::of::<u64>() {
-        ffi::WasmEdge_ProgramOptionType_UInt64
+        Some(ffi::WasmEdge_ProgramOptionType_UInt64)
     } else if type_id == TypeId::of::<f32>() {
-        ffi::WasmEdge_ProgramOptionType_Float
+        Some(ffi::WasmEdge_ProgramOptionType_Float)
     } else if type_id == TypeId::of::<f64>() {
-        ffi::WasmEdge_ProgramOptionType_Double
+        Some(ffi::WasmEdge_ProgramOptionType_Double)
     } else if type_id == TypeId::of::<OptionString>() {
-        ffi::WasmEdge_ProgramOptionType_String
+        Some(ffi::WasmEdge_ProgramOptionType_String)
+    } else {
+        None
+    }
+}
+
+type BoxedModuleCreateFn =
+    Box<dyn Fn(&ModuleCreateContext) -> *mut ffi::WasmEdge_ModuleInstanceContext + Send + Sync>;
+
+/// The version of a plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PluginVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    pub build: u32,
+}
+
+impl fmt::Display for PluginVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.patch, self.build
+        )
+    }
+}
+
+impl From<ffi::WasmEdge_PluginVersionData> for PluginVersion {
+    fn from(v: ffi::WasmEdge_PluginVersionData) -> Self {
+        Self {
+            major: v.Major,
+            minor: v.Minor,
+            patch: v.Patch,
+            build: v.Build,
+        }
+    }
+}
+
+/// What WasmEdge is creating when it calls the create function of a module.
+#[derive(Debug, Clone, Copy)]
+pub struct ModuleCreateContext<'a> {
+    module_name: &'a str,
+    module_description: &'a str,
+    plugin_name: &'a str,
+    plugin_version: PluginVersion,
+    runtime_version: RuntimeVersion,
+    runtime_supported: bool,
+    options: PluginOptions,
+}
+
+impl<'a> ModuleCreateContext<'a> {
+    /// Returns the name of the module being created, as registered in the plugin.
+    pub fn module_name(&self) -> &'a str {
+        self.module_name
+    }
+
+    pub fn module_description(&self) -> &'a str {
+        self.module_description
+    }
+
+    pub fn plugin_name(&self) -> &'a str {
+        self.plugin_name
+    }
+
+    pub fn plugin_version(&self) -> PluginVersion {
+        self.plugin_version
+    }
+
+    /// Returns the version of the WasmEdge runtime creating the module.
+    pub fn runtime_version(&self) -> RuntimeVersion {
+        self.runtime_version
+    }
+
+    /// Returns `false` if the runtime is outside the range declared with
+    /// [PluginBuilder::supported_runtime] and the plugin is loaded by [VersionPolicy::Warn], in
+    /// which case the module should leave out what the runtime can't do.
+    pub fn runtime_supported(&self) -> bool {
+        self.runtime_supported
+    }
+
+    /// Returns the program options of the plugin, as parsed by WasmEdge.
+    pub fn options(&self) -> PluginOptions {
+        self.options
+    }
+}
+
+/// A function creating a module, which takes either nothing or a [ModuleCreateContext].
+///
+/// `Args` only tells the two kinds of functions apart and is inferred.
+pub trait ModuleCreateFn<Args, M>: Send + Sync + 'static {
+    fn create(&self, ctx: &ModuleCreateContext) -> M;
+}
+
+impl<F, M> ModuleCreateFn<(), M> for F
+where
+    F: Fn() -> M + Send + Sync + 'static,
+{
+    fn create(&self, _: &ModuleCreateContext) -> M {
+        self()
+    }
+}
+
+impl<F, M> ModuleCreateFn<(ModuleCreateContext<'static>,), M> for F
+where
+    F: Fn(&ModuleCreateContext) -> M + Send + Sync + 'static,
+{
+    fn create(&self, ctx: &ModuleCreateContext) -> M {
+        self(ctx)
+    }
+}
+
+struct ModuleDef {
+    name: CString,
+    description: CString,
+    create: BoxedModuleCreateFn,
+}
+
+type OptionCheck = Box<dyn Fn(&PluginOptions) -> Result<(), OptionError> + Send + Sync>;
+
+type LoadHook = Box<dyn Fn() -> Result<(), String> + Send + Sync>;
+
+type UnloadHook = Box<dyn Fn() + Send + Sync>;
+
+struct OptionDef {
+    name: CString,
+    description: CString,
+    ty: ffi::WasmEdge_ProgramOptionType,
+    storage: *mut c_void,
+    default: *const c_void,
+}
+
+/// What a plugin does when the WasmEdge runtime loading it is outside its supported range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VersionPolicy {
+    /// Logs an error and hands WasmEdge a plugin without modules and options.
+    #[default]
+    Reject,
+    /// Logs a warning and loads the plugin, so modules can degrade by checking
+    /// [ModuleCreateContext::runtime_supported].
+    Warn,
+}
+
+/// Builds the descriptor of a plugin at runtime.
+///
+/// Modules and options can be added from a loop or from a config list. The plugin is exported
+/// with [export_plugin], which builds it once when WasmEdge retrieves the descriptor.
+pub struct PluginBuilder {
+    name: CString,
+    description: CString,
+    version: ffi::WasmEdge_PluginVersionData,
+    api_version: u32,
+    supported_runtime: (Bound<RuntimeVersion>, Bound<RuntimeVersion>),
+    supported_api: (Bound<u32>, Bound<u32>),
+    version_policy: VersionPolicy,
+    modules: Vec<ModuleDef>,
+    options: Vec<OptionDef>,
+    checks: Vec<OptionCheck>,
+    on_load: Option<LoadHook>,
+    on_unload: Option<UnloadHook>,
+}
+
+/// Converts a name into a C string, cutting it at the first interior nul byte.
+fn to_c_string(s: &str) -> CString {
+    let bytes = s.as_bytes();
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    CString::new(&bytes[..len]).unwrap_or_default()
+}
+
+impl PluginBuilder {
+    pub fn new(name: &str, description: &str) -> Self {
+        Self {
+            name: to_c_string(name),
+            description: to_c_string(description),
+            version: ffi::WasmEdge_PluginVersionData {
+                Major: 0,
+                Minor: 0,
+                Patch: 0,
+                Build: 0,
+            },
+            api_version: ffi::WasmEdge_Plugin_CurrentAPIVersion,
+            supported_runtime: (Bound::Unbounded, Bound::Unbounded),
+            supported_api: (Bound::Unbounded, Bound::Unbounded),
+            version_policy: VersionPolicy::default(),
+            modules: Vec::new(),
+            options: Vec::new(),
+            checks: Vec::new(),
+            on_load: None,
+            on_unload: None,
+        }
+    }
+
+    pub fn version(mut self, major: u32, minor: u32, patch: u32, build: u32) -> Self {
+        self.version = ffi::WasmEdge_PluginVersionData {
+            Major: major,
+            Minor: minor,
+            Patch: patch,
+            Build: build,
+        };
+        self
+    }
+
+    /// Sets the plugin API version written into the descriptor.
+    ///
+    /// It defaults to the version of the WasmEdge headers the SDK is compiled against. WasmEdge
+    /// refuses a plugin whose API version differs from its own.
+    pub fn api_version(mut self, api_version: u32) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
+    /// Declares the versions of the WasmEdge runtime the plugin works with, e.g.
+    /// `RuntimeVersion::new(0, 13, 0)..RuntimeVersion::new(0, 15, 0)`.
+    ///
+    /// The runtime is checked when WasmEdge retrieves the descriptor and when it creates a
+    /// module. Every version is supported by default.
+    pub fn supported_runtime(mut self, range: impl RangeBounds<RuntimeVersion>) -> Self {
+        self.supported_runtime = (range.start_bound().cloned(), range.end_bound().cloned());
+        self
+    }
+
+    /// Declares the plugin API versions the plugin works with, e.g. `2..=3`.
+    ///
+    /// The API version of the runtime is only known for the release series of the WasmEdge
+    /// headers, see [RuntimeVersion::plugin_api_version], and is checked with the runtime
+    /// version. Any other runtime is taken as unsupported once a range is declared. Every API
+    /// version is supported by default.
+    pub fn supported_api(mut self, range: impl RangeBounds<u32>) -> Self {
+        self.supported_api = (range.start_bound().cloned(), range.end_bound().cloned());
+        self
+    }
+
+    /// Sets what the plugin does on a runtime outside its supported ranges.
+    pub fn on_unsupported_runtime(mut self, policy: VersionPolicy) -> Self {
+        self.version_policy = policy;
+        self
+    }
+
+    /// Adds a module, which is created by `create` each time WasmEdge instantiates it.
+    ///
+    /// `create` may take a [ModuleCreateContext], so that one function can create several
+    /// modules, or configure them from the program options.
+    pub fn module<F, Args, M>(mut self, name: &str, description: &str, create: F) -> Self
+    where
+        F: ModuleCreateFn<Args, M>,
+        M: Into<*mut ffi::WasmEdge_ModuleInstanceContext>,
+    {
+        self.modules.push(ModuleDef {
+            name: to_c_string(name),
+            description: to_c_string(description),
+            create: Box::new(move |ctx| create.create(ctx).into()),
+        });
+        self
+    }
+
+    /// Adds a program option, which WasmEdge parses from its command line.
+    ///
+    /// The option holds `default` until then, so it can be read before WasmEdge parses the
+    /// options, e.g. while the manifest of the plugin is read.
+    ///
+    /// # Panics
+    ///
+    /// If `V` isn't a type of program option supported by WasmEdge.
+    pub fn option<V: Placeholder + 'static>(
+        mut self,
+        name: &str,
+        description: &str,
+        default: V,
+    ) -> Self {
+        self.options.push(OptionDef {
+            name: to_c_string(name),
+            description: to_c_string(description),
+            ty: select_type::<V>(),
+            storage: Box::into_raw(V::create_from_default(&default)).cast(),
+            default: Box::into_raw(Box::new(default)).cast_const().cast(),
+        });
+        self
+    }
+
+    /// Adds a string option holding a comma-separated list, e.g. `--dirs a,b,c`.
+    ///
+    /// Every item must parse as `T` when the plugin is loaded. Read it with
+    /// [PluginOptions::list].
+    pub fn list_option<T>(mut self, name: &str, description: &str, default: &[&str]) -> Self
+    where
+        T: FromStr + 'static,
+        T::Err: fmt::Display,
+    {
+        let option_name = name.to_string();
+        self.checks.push(Box::new(move |options| {
+            options.list::<T>(&option_name).map(|_| ())
+        }));
+        self.option(name, description, OptionString::from(default.join(",")))
+    }
+
+    /// Adds a string option holding one of the values of `E`, which is parsed with `FromStr`.
+    ///
+    /// The value must parse when the plugin is loaded. Read it with [PluginOptions::parse].
+    pub fn enum_option<E>(mut self, name: &str, description: &str, default: &str) -> Self
+    where
+        E: FromStr + 'static,
+        E::Err: fmt::Display,
+    {
+        let option_name = name.to_string();
+        self.checks.push(Box::new(move |options| {
+            options.parse::<E>(&option_name).map(|_| ())
+        }));
+        self.option(name, description, OptionString::from(default.to_string()))
+    }
+
+    /// Adds a string option holding a path, which must exist when the plugin is loaded unless
+    /// it is empty. Read it with [PluginOptions::path].
+    pub fn path_option(mut self, name: &str, description: &str, default: &str) -> Self {
+        let option_name = name.to_string();
+        self.checks.push(Box::new(move |options| {
+            let path = options.path(&option_name)?;
+            if path.as_os_str().is_empty() || path.exists() {
+                Ok(())
+            } else {
+                Err(OptionError::Invalid {
+                    name: option_name.clone(),
+                    value: path.display().to_string(),
+                    reason: "no such file or directory".to_string(),
+                })
+            }
+        }));
+        self.option(name, description, OptionString::from(default.to_string()))
+    }
+
+    /// Sets a function run once when WasmEdge first retrieves the descriptor, e.g. to set up
+    /// logging, thread pools or caches.
+    ///
+    /// If it fails or panics, the error is logged and WasmEdge gets a plugin without modules and
+    /// options.
+    pub fn on_load<F, E>(mut self, on_load: F) -> Self
+    where
+        F: Fn() -> Result<(), E> + Send + Sync + 'static,
+        E: fmt::Display,
+    {
+        self.on_load = Some(Box::new(move || on_load().map_err(|e| e.to_string())));
+        self
+    }
+
+    /// Sets a function run once when the library is unloaded, if the plugin was loaded.
+    ///
+    /// # Platforms
+    ///
+    /// It only runs by itself on Linux, Android and FreeBSD, where [export_plugin] hooks
+    /// [Plugin::unload] into `.fini_array`. On macOS, Windows and other targets it never runs
+    /// unless the plugin calls [Plugin::unload], e.g. from an `atexit` handler.
+    pub fn on_unload<F>(mut self, on_unload: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.on_unload = Some(Box::new(on_unload));
+        self
+    }
+
+    /// Builds the plugin and its descriptor, and registers the plugin for the `Create` callbacks
+    /// of its module descriptors.
+    ///
+    /// The plugin is leaked, so that the descriptor stays valid for as long as WasmEdge may use
+    /// it. Build it once, e.g. through [Plugin::get_or_init], which [export_plugin] uses.
+    pub fn build(self) -> &'static Plugin {
+        let module_descriptors = self
+            .modules
+            .iter()
+            .map(|module| ffi::WasmEdge_ModuleDescriptor {
+                Name: module.name.as_ptr(),
+                Description: module.description.as_ptr(),
+                Create: Some(create_module_wrap),
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        let program_options = self
+            .options
+            .iter()
+            .map(|option| ffi::WasmEdge_ProgramOption {
+                Name: option.name.as_ptr(),
+                Description: option.description.as_ptr(),
+                Type: option.ty,
+                Storage: option.storage,
+                DefaultValue: option.default,
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        let mut plugin = Plugin {
+            descriptor: Box::new(ffi::WasmEdge_PluginDescriptor {
+                Name: self.name.as_ptr(),
+                Description: self.description.as_ptr(),
+                APIVersion: self.api_version,
+                Version: self.version,
+                ModuleCount: 0,
+                ModuleDescriptions: std::ptr::null_mut(),
+                ProgramOptionCount: 0,
+                ProgramOptions: std::ptr::null_mut(),
+            }),
+            module_descriptors,
+            program_options,
+            empty_descriptor: Box::new(ffi::WasmEdge_PluginDescriptor {
+                Name: self.name.as_ptr(),
+                Description: self.description.as_ptr(),
+                APIVersion: self.api_version,
+                Version: self.version,
+                ModuleCount: 0,
+                ModuleDescriptions: std::ptr::null_mut(),
+                ProgramOptionCount: 0,
+                ProgramOptions: std::ptr::null_mut(),
+            }),
+            builder: self,
+            runtime: OnceLock::new(),
+            loaded: OnceLock::new(),
+            unloaded: Once::new(),
+        };
+        plugin.descriptor.ModuleCount = plugin.module_descriptors.len() as u32;
+        plugin.descriptor.ModuleDescriptions = plugin.module_descriptors.as_mut_ptr();
+        plugin.descriptor.ProgramOptionCount = plugin.program_options.len() as u32;
+        plugin.descriptor.ProgramOptions = plugin.program_options.as_mut_ptr();
+
+        let plugin: &'static Plugin = Box::leak(Box::new(plugin));
+        if let Ok(mut plugins) = BUILT_PLUGINS.lock() {
+            plugins.push(plugin);
+        }
+        plugin
+    }
+}
+
+/// A built plugin, which owns its descriptor and everything the descriptor points to.
+pub struct Plugin {
+    descriptor: Box<ffi::WasmEdge_PluginDescriptor>,
+    module_descriptors: Box<[ffi::WasmEdge_ModuleDescriptor]>,
+    program_options: Box<[ffi::WasmEdge_ProgramOption]>,
+    // Handed to an unsupported runtime instead of the descriptor.
+    empty_descriptor: Box<ffi::WasmEdge_PluginDescriptor>,
+    builder: PluginBuilder,
+    runtime: OnceLock<Result<RuntimeVersion, VersionError>>,
+    // Whether the load hook succeeded.
+    loaded: OnceLock<bool>,
+    unloaded: Once,
+}
+
+// The descriptor is never written after the plugin is built, and the option storage is only
+// written by WasmEdge while it parses its command line.
+unsafe impl Send for Plugin {}
+unsafe impl Sync for Plugin {}
+
+// The plugin handed to WasmEdge by `WasmEdge_Plugin_GetDescriptor`.
+static PLUGIN: OnceLock<&'static Plugin> = OnceLock::new();
+
+// Every built plugin, which the `Create` callback finds by the address of its module descriptor.
+static BUILT_PLUGINS: Mutex<Vec<&'static Plugin>> = Mutex::new(Vec::new());
+
+impl Plugin {
+    /// Returns the plugin of this library, building it with `builder_fn` the first time.
+    pub fn get_or_init(builder_fn: impl FnOnce() -> PluginBuilder) -> &'static Plugin {
+        PLUGIN.get_or_init(|| builder_fn().build())
+    }
+
+    /// Returns the plugin of this library if it is built.
+    pub fn get() -> Option<&'static Plugin> {
+        PLUGIN.get().copied()
+    }
+
+    // Returns the built plugin owning a module descriptor.
+    fn of_module(desc: *const ffi::WasmEdge_ModuleDescriptor) -> Option<&'static Plugin> {
+        let plugins = BUILT_PLUGINS.lock().ok()?;
+        plugins
+            .iter()
+            .copied()
+            .find(|plugin| plugin.module_descriptors.as_ptr_range().contains(&desc))
+    }
+
+    pub fn descriptor(&self) -> *const ffi::WasmEdge_PluginDescriptor {
+        &*self.descriptor
+    }
+
+    /// Returns the manifest of this plugin, creating each module once to list its exports.
+    ///
+    /// This runs the create function and the hooks of every module, see
+    /// [PluginManifest::from_descriptor]. Options WasmEdge hasn't parsed hold their defaults, so
+    /// the modules see the options a plain `wasmedge` run would.
+    ///
+    /// # Error
+    ///
+    /// If a module fails to be created, then an error is returned.
+    pub fn manifest(&self) -> Result<PluginManifest, InstanceError> {
+        unsafe { PluginManifest::from_descriptor(self.descriptor()) }
+    }
+
+    /// Returns the descriptor to hand to the loading runtime after checking its version and
+    /// running the load hook.
+    ///
+    /// An unsupported runtime gets a descriptor without modules and options under
+    /// [VersionPolicy::Reject], and so does any runtime if the load hook fails.
+    pub fn load_descriptor(&'static self) -> *const ffi::WasmEdge_PluginDescriptor {
+        if self.runtime_allowed() && self.load() {
+            self.descriptor()
+        } else {
+            &*self.empty_descriptor
+        }
+    }
+
+    // Runs the load hook, once, and returns whether it succeeded.
+    fn load(&self) -> bool {
+        *self.loaded.get_or_init(|| {
+            let Some(on_load) = &self.builder.on_load else {
+                return true;
+            };
+            let error = match panic::catch_unwind(AssertUnwindSafe(on_load)) {
+                Ok(Ok(())) => return true,
+                Ok(Err(e)) => e,
+                Err(payload) => panic_message(payload.as_ref()),
+            };
+            log::error!(
+                "Fail to load plugin `{}`: {}",
+                self.builder.name.to_string_lossy(),
+                error
+            );
+            false
+        })
+    }
+
+    /// Runs the unload hook of the plugin of this library, once, if its load hook has
+    /// succeeded.
+    ///
+    /// [export_plugin] calls it when the library is unloaded on Linux, Android and FreeBSD; call it
+    /// from the plugin on other targets, such as macOS and Windows.
+    pub fn unload() {
+        let Some(plugin) = Plugin::get() else {
+            return;
+        };
+        if plugin.loaded.get() != Some(&true) {
+            return;
+        }
+        plugin.unloaded.call_once(|| {
+            let Some(on_unload) = &plugin.builder.on_unload else {
+                return;
+            };
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(on_unload)) {
+                log::error!(
+                    "Fail to unload plugin `{}`: {}",
+                    plugin.builder.name.to_string_lossy(),
+                    panic_message(payload.as_ref())
+                );
+            }
+        });
+    }
+
+    /// Checks the version of the loading runtime, and its plugin API version, against the
+    /// supported ranges of the plugin.
+    ///
+    /// A runtime whose API version isn't known fails the check unless every API version is
+    /// supported.
+    pub fn check_runtime(&self) -> Result<RuntimeVersion, VersionError> {
+        self.runtime
+            .get_or_init(|| {
+                let plugin = self.builder.name.to_string_lossy().into_owned();
+                let runtime = RuntimeVersion::current();
+                if !self.builder.supported_runtime.contains(&runtime) {
+                    return Err(VersionError::UnsupportedRuntime {
+                        plugin,
+                        runtime,
+                        supported: fmt_range(&self.builder.supported_runtime),
+                    });
+                }
+                match runtime.plugin_api_version() {
+                    Some(api) if !self.builder.supported_api.contains(&api) => {
+                        Err(VersionError::UnsupportedApi {
+                            plugin,
+                            runtime,
+                            api,
+                            supported: fmt_range(&self.builder.supported_api),
+                        })
+                    }
+                    None if self.builder.supported_api != (Bound::Unbounded, Bound::Unbounded) => {
+                        Err(VersionError::UnknownApi {
+                            plugin,
+                            runtime,
+                            supported: fmt_range(&self.builder.supported_api),
+                        })
+                    }
+                    _ => Ok(runtime),
+                }
+            })
+            .clone()
+    }
+
+    // Logs an unsupported runtime, once, and returns whether the plugin may be used.
+    fn runtime_allowed(&self) -> bool {
+        let first = self.runtime.get().is_none();
+        match (self.check_runtime(), self.builder.version_policy) {
+            (Ok(_), _) => true,
+            (Err(e), VersionPolicy::Reject) => {
+                if first {
+                    log::error!("{e}");
+                }
+                false
+            }
+            (Err(e), VersionPolicy::Warn) => {
+                if first {
+                    log::warn!("{e}");
+                }
+                true
+            }
+        }
+    }
+
+    /// Returns the storage of a program option, or null if there is no such option of type `V`.
+    pub fn option_storage<V: 'static>(&'static self, name: &str) -> *const V {
+        match self.options().get::<V>(name) {
+            Some(v) => v,
+            None => std::ptr::null(),
+        }
+    }
+
+    /// Returns the program options of this plugin.
+    pub fn options(&'static self) -> PluginOptions {
+        PluginOptions {
+            options: &self.program_options,
+        }
+    }
+
+    /// Checks the values of the list, enum and path options.
+    ///
+    /// WasmEdge parses the options after it loads the plugin, and a module may be created before
+    /// that, e.g. to read the manifest, so they are checked whenever a module is created rather
+    /// than once. A module isn't created while the options are invalid.
+    pub fn check_options(&'static self) -> Result<(), OptionError> {
+        let options = self.options();
+        self.builder
+            .checks
+            .iter()
+            .try_for_each(|check| check(&options))
+    }
+
+    fn create_module(
+        &'static self,
+        desc: *const ffi::WasmEdge_ModuleDescriptor,
+    ) -> *mut ffi::WasmEdge_ModuleInstanceContext {
+        if !self.runtime_allowed() {
+            return std::ptr::null_mut();
+        }
+        if let Err(e) = self.check_options() {
+            log::error!(
+                "Fail to load plugin `{}`: {}",
+                self.builder.name.to_string_lossy(),
+                e
+            );
+            return std::ptr::null_mut();
+        }
+
+        // WasmEdge hands back the descriptor it got, but fall back to the name for a copy of it.
+        let idx = self
+            .module_descriptors
+            .iter()
+            .position(|d| std::ptr::eq(d, desc))
+            .or_else(|| {
+                let name = unsafe { CStr::from_ptr(desc.as_ref()?.Name) };
+                self.builder
+                    .modules
+                    .iter()
+                    .position(|m| m.name.as_c_str() == name)
+            });
+        let Some(module) = idx.map(|idx| &self.builder.modules[idx]) else {
+            return std::ptr::null_mut();
+        };
+        let ctx = ModuleCreateContext {
+            module_name: module.name.to_str().unwrap_or_default(),
+            module_description: module.description.to_str().unwrap_or_default(),
+            plugin_name: self.builder.name.to_str().unwrap_or_default(),
+            plugin_version: self.builder.version.into(),
+            runtime_version: RuntimeVersion::current(),
+            runtime_supported: self.check_runtime().is_ok(),
+            options: self.options(),
+        };
+        (module.create)(&ctx)
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
     } else {
-        panic!("Unsupported option type")
+        "panicked".to_string()
+    }
+}
+
+fn fmt_range<T: fmt::Display>(range: &(Bound<T>, Bound<T>)) -> String {
+    let start = match &range.0 {
+        Bound::Included(v) => Some(format!(">={v}")),
+        Bound::Excluded(v) => Some(format!(">{v}")),
+        Bound::Unbounded => None,
+    };
+    let end = match &range.1 {
+        Bound::Included(v) => Some(format!("<={v}")),
+        Bound::Excluded(v) => Some(format!("<{v}")),
+        Bound::Unbounded => None,
+    };
+    match (start, end) {
+        (Some(start), Some(end)) => format!("{start}, {end}"),
+        (Some(bound), None) | (None, Some(bound)) => bound,
+        (None, None) => "any version".to_string(),
+    }
+}
+
+// WasmEdge hands back the module descriptor it got, which leads to the plugin that built it, or
+// else a copy of it, which is looked up in the plugin of this library.
+unsafe extern "C" fn create_module_wrap(
+    desc: *const ffi::WasmEdge_ModuleDescriptor,
+) -> *mut ffi::WasmEdge_ModuleInstanceContext {
+    match Plugin::of_module(desc).or_else(Plugin::get) {
+        Some(plugin) => plugin.create_module(desc),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// The program options of the loaded plugin, as parsed by WasmEdge.
+#[derive(Debug, Clone, Copy)]
+pub struct PluginOptions {
+    options: &'static [ffi::WasmEdge_ProgramOption],
+}
+
+impl PluginOptions {
+    /// Returns the program options of the plugin handed to WasmEdge.
+    ///
+    /// It is empty before WasmEdge has retrieved the plugin descriptor.
+    pub fn current() -> Self {
+        match Plugin::get() {
+            Some(plugin) => plugin.options(),
+            None => Self { options: &[] },
+        }
+    }
+
+    /// Returns the names of the program options.
+    pub fn names(&self) -> Vec<String> {
+        self.options
+            .iter()
+            .filter_map(|option| unsafe { CStr::from_ptr(option.Name) }.to_str().ok())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Returns the value of a program option by name, or a copy of it for a string option.
+    pub fn value<V: OptionValue>(&self, name: &str) -> Result<V::Value, OptionError> {
+        match self.get::<V>(name) {
+            Some(storage) => Ok(V::read(storage)),
+            None if self.names().iter().any(|n| n == name) => {
+                Err(OptionError::TypeMismatch(name.to_string()))
+            }
+            None => Err(OptionError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Returns the value of a string option by name.
+    pub fn string(&self, name: &str) -> Result<String, OptionError> {
+        self.value::<OptionString>(name)
+    }
+
+    /// Parses the value of a string option, e.g. an option added by
+    /// [PluginBuilder::enum_option].
+    pub fn parse<T>(&self, name: &str) -> Result<T, OptionError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let value = self.string(name)?;
+        parse_option(name, value.trim())
+    }
+
+    /// Parses the items of a comma-separated string option, e.g. an option added by
+    /// [PluginBuilder::list_option]. Empty items are skipped.
+    pub fn list<T>(&self, name: &str) -> Result<Vec<T>, OptionError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.string(name)?
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| parse_option(name, item))
+            .collect()
+    }
+
+    /// Returns the value of a string option as a path, e.g. an option added by
+    /// [PluginBuilder::path_option].
+    pub fn path(&self, name: &str) -> Result<PathBuf, OptionError> {
+        self.string(name).map(PathBuf::from)
+    }
+
+    /// Returns the value of a program option as text if it was given, or `None` if there is no
+    /// such option or it wasn't given.
+    ///
+    /// An option was given if it doesn't hold its default value, or if `--<name>` or
+    /// `--<name>=<value>` is an argument of the process, from which WasmEdge parses its options,
+    /// so `--threads 4` with a default of 4 is still given.
+    ///
+    /// # Arguments of the guest
+    ///
+    /// WasmEdge passes the arguments after the wasm file to the guest, which can't be told from
+    /// its own, so a guest argument named like the option also makes it given, unless it comes
+    /// after `--`.
+    pub fn explicit(&self, name: &str) -> Option<String> {
+        let option = self
+            .options
+            .iter()
+            .find(|option| unsafe { CStr::from_ptr(option.Name) }.to_bytes() == name.as_bytes())?;
+        let value = unsafe { option_text(option.Type, option.Storage) }?;
+        let default = unsafe { option_text(option.Type, option.DefaultValue) };
+        let given =
+            default.as_ref() != Some(&value) || has_option_arg(std::env::args_os().skip(1), name);
+        given.then_some(value)
+    }
+
+    /// Returns the value of a program option by name.
+    ///
+    /// `None` is returned if there is no such option or if its type isn't `V`.
+    pub fn get<V: 'static>(&self, name: &str) -> Option<&'static V> {
+        let ty = try_select_type::<V>()?;
+        let option = self
+            .options
+            .iter()
+            .find(|option| unsafe { CStr::from_ptr(option.Name) }.to_bytes() == name.as_bytes())?;
+        if option.Type != ty {
+            return None;
+        }
+        unsafe { option.Storage.cast::<V>().as_ref() }
+    }
+}
+
+// Returns whether `--<name>` or `--<name>=<value>` is among the arguments, up to `--`.
+fn has_option_arg(args: impl IntoIterator<Item = OsString>, name: &str) -> bool {
+    let flag = format!("--{name}");
+    args.into_iter().take_while(|arg| arg != "--").any(|arg| {
+        arg.to_str().is_some_and(|arg| {
+            arg.strip_prefix(&flag)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('='))
+        })
+    })
+}
+
+/// Formats the value of a program option of type `ty` stored at `v`.
+pub(crate) unsafe fn option_text(
+    ty: ffi::WasmEdge_ProgramOptionType,
+    v: *const c_void,
+) -> Option<String> {
+    if v.is_null() {
+        return None;
     }
+    let s = match ty {
+        ffi::WasmEdge_ProgramOptionType_Toggle => (*v.cast::<bool>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_Int8 => (*v.cast::<i8>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_Int16 => (*v.cast::<i16>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_Int32 => (*v.cast::<i32>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_Int64 => (*v.cast::<i64>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_UInt8 => (*v.cast::<u8>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_UInt16 => (*v.cast::<u16>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_UInt32 => (*v.cast::<u32>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_UInt64 => (*v.cast::<u64>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_Float => (*v.cast::<f32>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_Double => (*v.cast::<f64>()).to_string(),
+        ffi::WasmEdge_ProgramOptionType_String => {
+            String::from_utf8_lossy((*v.cast::<OptionString>()).as_bytes()).into_owned()
+        }
+        _ => return None,
+    };
+    Some(s)
+}
+
+fn parse_option<T>(name: &str, value: &str) -> Result<T, OptionError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value.parse().map_err(|e: T::Err| OptionError::Invalid {
+        name: name.to_string(),
+        value: value.to_string(),
+        reason: e.to_string(),
+    })
 }
 
 #[macro_export]
@@ -244,5 +1155,127 @@ macro_rules! option_string {
         }
     };
 }
+pub use export_plugin;
 pub use option_string;
 pub use register_plugin;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+    fn create() -> *mut ffi::WasmEdge_ModuleInstanceContext {
+        CREATED.fetch_add(1, Ordering::SeqCst);
+        std::ptr::null_mut()
+    }
+
+    #[test]
+    fn binds_module_descriptors_to_the_built_plugin() {
+        let plugin = PluginBuilder::new("built", "a plugin built without get_or_init")
+            .module("a", "module a", create)
+            .module("b", "module b", create)
+            .build();
+        let other = PluginBuilder::new("other", "another plugin").build();
+
+        let descriptor = unsafe { &*plugin.descriptor() };
+        let modules = unsafe { slice::from_raw_parts(descriptor.ModuleDescriptions, 2) };
+        for module in modules {
+            let found = Plugin::of_module(module).unwrap();
+            assert!(std::ptr::eq(found, plugin));
+            assert!(!std::ptr::eq(found, other));
+        }
+        assert!(Plugin::of_module(std::ptr::null()).is_none());
+
+        let before = CREATED.load(Ordering::SeqCst);
+        let module = unsafe { modules[1].Create.unwrap()(&modules[1]) };
+        assert!(module.is_null());
+        assert_eq!(CREATED.load(Ordering::SeqCst), before + 1);
+    }
+
+    #[test]
+    fn finds_options_given_on_the_command_line() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert!(has_option_arg(
+            args(&["--threads", "4", "app.wasm"]),
+            "threads"
+        ));
+        assert!(has_option_arg(
+            args(&["--threads=4", "app.wasm"]),
+            "threads"
+        ));
+        assert!(has_option_arg(args(&["--verbose"]), "verbose"));
+        assert!(!has_option_arg(args(&["--threads-max", "4"]), "threads"));
+        assert!(!has_option_arg(args(&["-threads", "4"]), "threads"));
+        assert!(!has_option_arg(
+            args(&["app.wasm", "--", "--threads"]),
+            "threads"
+        ));
+    }
+
+    #[test]
+    fn knows_the_plugin_api_of_the_headers_series_only() {
+        let headers = RuntimeVersion::headers();
+        let patch = RuntimeVersion::new(headers.major, headers.minor, headers.patch + 1);
+        let next = RuntimeVersion::new(headers.major, headers.minor + 1, 0);
+        let api = ffi::WasmEdge_Plugin_CurrentAPIVersion;
+        assert_eq!(headers.plugin_api_version(), Some(api));
+        assert_eq!(patch.plugin_api_version(), Some(api));
+        assert_eq!(next.plugin_api_version(), None);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Mode {
+        Fast,
+        Slow,
+    }
+
+    impl FromStr for Mode {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "fast" => Ok(Mode::Fast),
+                "slow" => Ok(Mode::Slow),
+                _ => Err(format!("unknown mode `{s}`")),
+            }
+        }
+    }
+
+    #[test]
+    fn holds_defaults_until_options_are_parsed() {
+        let plugin = PluginBuilder::new("defaults", "a plugin with unparsed options")
+            .option("threads", "number of threads", 4u32)
+            .enum_option::<Mode>("mode", "run mode", "slow")
+            .list_option::<u16>("ports", "listening ports", &["80", "443"])
+            .build();
+        let options = plugin.options();
+
+        assert_eq!(options.value::<u32>("threads"), Ok(&4));
+        assert_eq!(options.parse::<Mode>("mode"), Ok(Mode::Slow));
+        assert_eq!(options.list::<u16>("ports"), Ok(vec![80, 443]));
+        assert_eq!(plugin.check_options(), Ok(()));
+    }
+
+    #[test]
+    fn checks_options_again_once_they_are_parsed() {
+        let plugin = PluginBuilder::new("parsed", "a plugin whose options change")
+            .enum_option::<Mode>("mode", "run mode", "slow")
+            .build();
+        assert_eq!(plugin.check_options(), Ok(()));
+
+        // Stand in for WasmEdge, which points the storage at the parsed string.
+        let storage = plugin.program_options[0].Storage.cast::<OptionString>();
+        unsafe { *storage = OptionString::from("turbo") };
+        assert!(matches!(
+            plugin.check_options(),
+            Err(OptionError::Invalid { .. })
+        ));
+
+        unsafe { *storage = OptionString::from("fast") };
+        assert_eq!(plugin.check_options(), Ok(()));
+        assert_eq!(plugin.options().parse::<Mode>("mode"), Ok(Mode::Fast));
+    }
+}
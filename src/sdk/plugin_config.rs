@@ -0,0 +1,619 @@
+//! Defines PluginConfig, a typed configuration of a plugin read from several layers.
+//!
+//! Each key of a configuration is looked up, in priority order, in the program options of the
+//! plugin, in the `WASMEDGE_PLUGIN_<NAME>_<KEY>` environment variables and in an optional TOML or
+//! JSON file. A key found nowhere keeps its default value.
+//!
+//! ```ignore
+//! plugin_config! {
+//!     pub struct Config {
+//!         threads: u32 = 4u32,
+//!         cache_dir: PathBuf = "/tmp/cache",
+//!         models: Vec<String> = Vec::new(),
+//!     }
+//! }
+//!
+//! let config: Config = ConfigLoader::new("my_plugin")
+//!     .options(PluginOptions::current())
+//!     .load()?;
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::error::ConfigError;
+use crate::sdk::plugin::PluginOptions;
+
+/// Defines a struct with default values and implements [PluginConfig] for it.
+///
+/// Every field type must implement [ConfigValue] and its default value must convert into it.
+#[macro_export]
+macro_rules! plugin_config {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident : $ty:ty = $default:expr),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty),*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    $($field: $default.into()),*
+                }
+            }
+        }
+
+        impl wasmedge_plugin_sdk::plugin_config::PluginConfig for $name {
+            const KEYS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+                match key {
+                    $(stringify!($field) => {
+                        self.$field =
+                            <$ty as wasmedge_plugin_sdk::plugin_config::ConfigValue>::parse_value(
+                                value,
+                            )?;
+                        Ok(())
+                    })*
+                    _ => Err(format!("unknown key `{key}`")),
+                }
+            }
+
+            fn set_list(&mut self, key: &str, items: &[String]) -> Result<(), String> {
+                match key {
+                    $(stringify!($field) => {
+                        self.$field =
+                            <$ty as wasmedge_plugin_sdk::plugin_config::ConfigValue>::parse_items(
+                                items,
+                            )?;
+                        Ok(())
+                    })*
+                    _ => Err(format!("unknown key `{key}`")),
+                }
+            }
+        }
+    };
+}
+pub use plugin_config;
+
+/// A configuration of a plugin, usually defined by [plugin_config!].
+pub trait PluginConfig: Default {
+    /// The keys of the configuration, in `snake_case`.
+    const KEYS: &'static [&'static str];
+
+    /// Parses `value` and sets it to the field of `key`.
+    ///
+    /// # Error
+    ///
+    /// If `value` isn't valid for the field, then the reason is returned.
+    fn set(&mut self, key: &str, value: &str) -> Result<(), String>;
+
+    /// Parses the items of an array of a config file and sets them to the field of `key`.
+    ///
+    /// By default, the items are joined by `,` and set with [PluginConfig::set].
+    ///
+    /// # Error
+    ///
+    /// If the items aren't valid for the field, then the reason is returned.
+    fn set_list(&mut self, key: &str, items: &[String]) -> Result<(), String> {
+        self.set(key, &items.join(","))
+    }
+}
+
+/// A type of a field of a [PluginConfig], parsed from text.
+pub trait ConfigValue: Sized {
+    fn parse_value(value: &str) -> Result<Self, String>;
+
+    /// Parses the items of an array of a config file.
+    ///
+    /// Only lists accept arrays by default.
+    fn parse_items(items: &[String]) -> Result<Self, String> {
+        let _ = items;
+        Err("expected a single value, not an array".to_string())
+    }
+}
+
+macro_rules! impl_config_value {
+    ($($ty:ty),*) => {
+        $(impl ConfigValue for $ty {
+            fn parse_value(value: &str) -> Result<Self, String> {
+                value.trim().parse().map_err(|e| format!("{e}"))
+            }
+        })*
+    };
+}
+
+impl_config_value!(i8, i16, i32, i64, u8, u16, u32, u64, usize, f32, f64);
+
+impl ConfigValue for bool {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(true),
+            "false" | "0" | "no" | "off" => Ok(false),
+            _ => Err("expected true or false".to_string()),
+        }
+    }
+}
+
+impl ConfigValue for String {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        Ok(value.to_string())
+    }
+}
+
+impl ConfigValue for PathBuf {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        Ok(PathBuf::from(value))
+    }
+}
+
+/// A comma-separated list. Empty items are skipped.
+impl<T: ConfigValue> ConfigValue for Vec<T> {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(T::parse_value)
+            .collect()
+    }
+
+    /// Every item of an array is parsed as is, commas included.
+    fn parse_items(items: &[String]) -> Result<Self, String> {
+        items.iter().map(|item| T::parse_value(item)).collect()
+    }
+}
+
+/// An empty value is `None`.
+impl<T: ConfigValue> ConfigValue for Option<T> {
+    fn parse_value(value: &str) -> Result<Self, String> {
+        if value.trim().is_empty() {
+            Ok(None)
+        } else {
+            T::parse_value(value).map(Some)
+        }
+    }
+
+    fn parse_items(items: &[String]) -> Result<Self, String> {
+        T::parse_items(items).map(Some)
+    }
+}
+
+/// Where the value of a key of a configuration came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// The program option of the name.
+    Option(String),
+    /// The environment variable of the name.
+    Env(String),
+    /// The configuration file at the path.
+    File(PathBuf),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Option(name) => write!(f, "program option `{name}`"),
+            ConfigSource::Env(name) => write!(f, "environment variable `{name}`"),
+            ConfigSource::File(path) => write!(f, "config file `{}`", path.display()),
+        }
+    }
+}
+
+/// Reads a [PluginConfig] from the program options, the environment and a config file.
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    env_prefix: String,
+    options: Option<PluginOptions>,
+    file: Option<PathBuf>,
+}
+
+impl ConfigLoader {
+    /// Creates a loader for the plugin of the name, whose environment variables are prefixed
+    /// with `WASMEDGE_PLUGIN_<NAME>_`, where `<NAME>` is the name in upper case with every other
+    /// character than a letter or a digit replaced by `_`.
+    pub fn new(plugin_name: &str) -> Self {
+        let name: String = plugin_name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Self {
+            env_prefix: format!("WASMEDGE_PLUGIN_{name}_"),
+            options: None,
+            file: None,
+        }
+    }
+
+    /// Reads the keys from the program options, whose names are the keys in `snake_case` or
+    /// `kebab-case`.
+    ///
+    /// Only the options which were given override the environment and the config file, see
+    /// [PluginOptions::explicit](crate::plugin::PluginOptions::explicit).
+    pub fn options(mut self, options: PluginOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    /// Reads the keys from a config file, in TOML if its extension is `toml` and in JSON
+    /// otherwise.
+    ///
+    /// Without it, the file at the path in the `WASMEDGE_PLUGIN_<NAME>_CONFIG` environment
+    /// variable is read, if set.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Returns the prefix of the environment variables of the plugin.
+    pub fn env_prefix(&self) -> &str {
+        &self.env_prefix
+    }
+
+    /// Reads the configuration.
+    ///
+    /// # Error
+    ///
+    /// If the config file can't be read, a key of it is unknown, or a value isn't valid for its
+    /// field, then an error telling where the value came from is returned.
+    pub fn load<C: PluginConfig>(&self) -> Result<C, ConfigError> {
+        let file = self
+            .file
+            .clone()
+            .or_else(|| std::env::var_os(format!("{}CONFIG", self.env_prefix)).map(PathBuf::from));
+        let file_values = match &file {
+            Some(path) => read_file(path)?,
+            None => HashMap::new(),
+        };
+        if let Some(path) = &file {
+            let mut keys: Vec<_> = file_values.keys().collect();
+            keys.sort();
+            if let Some(key) = keys
+                .into_iter()
+                .find(|key| !C::KEYS.contains(&key.as_str()))
+            {
+                return Err(ConfigError::UnknownKey {
+                    key: key.clone(),
+                    origin: ConfigSource::File(path.clone()),
+                });
+            }
+        }
+
+        let mut config = C::default();
+        for &key in C::KEYS {
+            let Some((value, origin)) = self
+                .option_value(key)
+                .or_else(|| self.env_value(key))
+                .map(|(value, origin)| (RawValue::Text(value), origin))
+                .or_else(|| {
+                    let value = file_values.get(key)?.clone();
+                    Some((value, ConfigSource::File(file.clone()?)))
+                })
+            else {
+                continue;
+            };
+            let set = match &value {
+                RawValue::Text(text) => config.set(key, text),
+                RawValue::List(items) => config.set_list(key, items),
+            };
+            set.map_err(|reason| ConfigError::Invalid {
+                key: key.to_string(),
+                value: value.to_string(),
+                origin,
+                reason,
+            })?;
+        }
+        Ok(config)
+    }
+
+    fn option_value(&self, key: &str) -> Option<(String, ConfigSource)> {
+        let options = self.options.as_ref()?;
+        [key.to_string(), key.replace('_', "-")]
+            .into_iter()
+            .find_map(|name| Some((options.explicit(&name)?, ConfigSource::Option(name))))
+    }
+
+    fn env_value(&self, key: &str) -> Option<(String, ConfigSource)> {
+        let name = format!("{}{}", self.env_prefix, key.to_ascii_uppercase());
+        let value = std::env::var(&name).ok()?;
+        Some((value, ConfigSource::Env(name)))
+    }
+}
+
+/// A value of a config file as text.
+#[derive(Debug, Clone)]
+enum RawValue {
+    Text(String),
+    /// The items of an array, each kept whole.
+    List(Vec<String>),
+}
+
+impl fmt::Display for RawValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RawValue::Text(text) => f.write_str(text),
+            RawValue::List(items) => write!(f, "{items:?}"),
+        }
+    }
+}
+
+/// Reads a config file into its keys, in `snake_case`, and their values.
+///
+/// Nested tables are flattened into keys joined by `_`.
+fn read_file(path: &Path) -> Result<HashMap<String, RawValue>, ConfigError> {
+    let fail = |reason: String| ConfigError::File {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let text = std::fs::read_to_string(path).map_err(|e| fail(e.to_string()))?;
+    let value = if path.extension().is_some_and(|ext| ext == "toml") {
+        let table: toml::Table = text
+            .parse()
+            .map_err(|e: toml::de::Error| fail(e.to_string()))?;
+        toml_to_json(toml::Value::Table(table))
+    } else {
+        serde_json::from_str(&text).map_err(|e| fail(e.to_string()))?
+    };
+    let serde_json::Value::Object(map) = value else {
+        return Err(fail("the top level isn't a table".to_string()));
+    };
+
+    let mut values = HashMap::new();
+    flatten("", map, &mut values).map_err(fail)?;
+    Ok(values)
+}
+
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => i.into(),
+        toml::Value::Float(f) => f.into(),
+        toml::Value::Boolean(b) => b.into(),
+        toml::Value::Datetime(d) => serde_json::Value::String(d.to_string()),
+        toml::Value::Array(items) => items.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => serde_json::Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn flatten(
+    prefix: &str,
+    map: serde_json::Map<String, serde_json::Value>,
+    values: &mut HashMap<String, RawValue>,
+) -> Result<(), String> {
+    for (key, value) in map {
+        let key = format!("{prefix}{}", key.replace('-', "_"));
+        match value {
+            serde_json::Value::Object(map) => flatten(&format!("{key}_"), map, values)?,
+            serde_json::Value::Array(items) => {
+                let items = items
+                    .into_iter()
+                    .map(|item| scalar(&key, item))
+                    .collect::<Result<Vec<_>, _>>()?;
+                values.insert(key, RawValue::List(items));
+            }
+            value => {
+                let value = scalar(&key, value)?;
+                values.insert(key, RawValue::Text(value));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn scalar(key: &str, value: serde_json::Value) -> Result<String, String> {
+    match value {
+        serde_json::Value::Null => Ok(String::new()),
+        serde_json::Value::String(s) => Ok(s),
+        serde_json::Value::Bool(b) => Ok(b.to_string()),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        _ => Err(format!("the value of `{key}` is nested too deep")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::{OptionString, Plugin, PluginBuilder};
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Config {
+        threads: u32,
+        cache_dir: String,
+        model: String,
+        verbose: bool,
+        tags: Vec<String>,
+    }
+
+    impl PluginConfig for Config {
+        const KEYS: &'static [&'static str] = &["threads", "cache_dir", "model", "verbose", "tags"];
+
+        fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+            match key {
+                "threads" => self.threads = ConfigValue::parse_value(value)?,
+                "cache_dir" => self.cache_dir = ConfigValue::parse_value(value)?,
+                "model" => self.model = ConfigValue::parse_value(value)?,
+                "verbose" => self.verbose = ConfigValue::parse_value(value)?,
+                "tags" => self.tags = ConfigValue::parse_value(value)?,
+                _ => return Err(format!("unknown key `{key}`")),
+            }
+            Ok(())
+        }
+
+        fn set_list(&mut self, key: &str, items: &[String]) -> Result<(), String> {
+            match key {
+                "threads" => self.threads = ConfigValue::parse_items(items)?,
+                "cache_dir" => self.cache_dir = ConfigValue::parse_items(items)?,
+                "model" => self.model = ConfigValue::parse_items(items)?,
+                "verbose" => self.verbose = ConfigValue::parse_items(items)?,
+                "tags" => self.tags = ConfigValue::parse_items(items)?,
+                _ => return Err(format!("unknown key `{key}`")),
+            }
+            Ok(())
+        }
+    }
+
+    fn write_file(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "wasmedge-plugin-config-{}-{name}",
+            std::process::id()
+        ));
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn plugin(name: &str) -> &'static Plugin {
+        PluginBuilder::new(name, "a plugin with options")
+            .option("threads", "number of threads", 2u32)
+            .option("cache-dir", "cache directory", OptionString::from(""))
+            .option("verbose", "verbose output", OptionString::from(""))
+            .build()
+    }
+
+    // Stands in for WasmEdge, which writes the parsed value into the storage.
+    fn set_option<V>(plugin: &Plugin, index: usize, value: V) {
+        unsafe {
+            let option = &*(*plugin.descriptor()).ProgramOptions.add(index);
+            *option.Storage.cast::<V>() = value;
+        }
+    }
+
+    #[test]
+    fn layers_options_over_env_over_file() {
+        let plugin = plugin("layers");
+        set_option(plugin, 0, 4u32);
+        std::env::set_var("WASMEDGE_PLUGIN_CONFIG_LAYERS_THREADS", "6");
+        std::env::set_var("WASMEDGE_PLUGIN_CONFIG_LAYERS_CACHE_DIR", "/env");
+        let file = write_file(
+            "layers.json",
+            r#"{"threads": 8, "cache-dir": "/file", "model": "file"}"#,
+        );
+
+        let config: Config = ConfigLoader::new("config-layers")
+            .options(plugin.options())
+            .file(&file)
+            .load()
+            .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                threads: 4,
+                cache_dir: "/env".to_string(),
+                model: "file".to_string(),
+                ..Config::default()
+            }
+        );
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn flattens_toml_and_json_files() {
+        let toml = write_file(
+            "flatten.toml",
+            "threads = 3\ntags = [\"a,b\", \"c\"]\n\n[cache]\ndir = \"/toml\"\n",
+        );
+        let json = write_file(
+            "flatten.json",
+            r#"{"threads": 5, "tags": ["x"], "cache": {"dir": "/json"}, "verbose": true}"#,
+        );
+
+        let loader = ConfigLoader::new("config-flatten");
+        let config: Config = loader.clone().file(&toml).load().unwrap();
+        assert_eq!(
+            config,
+            Config {
+                threads: 3,
+                cache_dir: "/toml".to_string(),
+                tags: vec!["a,b".to_string(), "c".to_string()],
+                ..Config::default()
+            }
+        );
+        let config: Config = loader.file(&json).load().unwrap();
+        assert_eq!(
+            config,
+            Config {
+                threads: 5,
+                cache_dir: "/json".to_string(),
+                verbose: true,
+                tags: vec!["x".to_string()],
+                ..Config::default()
+            }
+        );
+        std::fs::remove_file(toml).unwrap();
+        std::fs::remove_file(json).unwrap();
+    }
+
+    #[test]
+    fn tells_where_an_invalid_value_came_from() {
+        let plugin = plugin("errors");
+        set_option(plugin, 2, OptionString::from("maybe"));
+        let loader = ConfigLoader::new("config-errors").options(plugin.options());
+        assert_eq!(
+            loader.load::<Config>(),
+            Err(ConfigError::Invalid {
+                key: "verbose".to_string(),
+                value: "maybe".to_string(),
+                origin: ConfigSource::Option("verbose".to_string()),
+                reason: "expected true or false".to_string(),
+            })
+        );
+
+        std::env::set_var("WASMEDGE_PLUGIN_CONFIG_ERRORS_THREADS", "many");
+        assert_eq!(
+            ConfigLoader::new("config-errors").load::<Config>(),
+            Err(ConfigError::Invalid {
+                key: "threads".to_string(),
+                value: "many".to_string(),
+                origin: ConfigSource::Env("WASMEDGE_PLUGIN_CONFIG_ERRORS_THREADS".to_string()),
+                reason: "invalid digit found in string".to_string(),
+            })
+        );
+
+        let file = write_file("errors.toml", "model = [\"a\"]\n");
+        assert_eq!(
+            ConfigLoader::new("config-file-errors")
+                .file(&file)
+                .load::<Config>(),
+            Err(ConfigError::Invalid {
+                key: "model".to_string(),
+                value: "[\"a\"]".to_string(),
+                origin: ConfigSource::File(file.clone()),
+                reason: "expected a single value, not an array".to_string(),
+            })
+        );
+
+        std::fs::write(&file, "[model]\nsize = 1\n").unwrap();
+        assert_eq!(
+            ConfigLoader::new("config-file-errors")
+                .file(&file)
+                .load::<Config>(),
+            Err(ConfigError::UnknownKey {
+                key: "model_size".to_string(),
+                origin: ConfigSource::File(file.clone()),
+            })
+        );
+
+        std::fs::write(&file, "threads = ").unwrap();
+        let error = ConfigLoader::new("config-file-errors")
+            .file(&file)
+            .load::<Config>()
+            .unwrap_err();
+        assert!(matches!(error, ConfigError::File { path, .. } if path == file));
+        std::fs::remove_file(file).unwrap();
+    }
+}
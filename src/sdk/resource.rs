@@ -0,0 +1,260 @@
+//! Defines ResourceTable, which hands out integer handles to host objects.
+
+use crate::error::ResourceError;
+
+// A handle packs the generation of its slot above the index of the slot, and is always positive,
+// so that guests can keep using negative numbers as error codes.
+const INDEX_BITS: u32 = 20;
+const INDEX_MASK: u32 = (1 << INDEX_BITS) - 1;
+const MAX_GENERATION: u32 = (1 << (31 - INDEX_BITS)) - 1;
+
+#[derive(Debug)]
+struct Slot<R> {
+    generation: u32,
+    value: Option<R>,
+}
+
+/// A table of host objects owned by a module, which the guest refers to by `i32` handles.
+///
+/// Every removal bumps the generation of the slot, so a stale handle to a removed object is
+/// rejected instead of reaching the object which reuses the slot. Keep the table in the host
+/// data of a [PluginModule](crate::module::PluginModule): the objects still in it are dropped
+/// with the host data when WasmEdge drops the module instance.
+#[derive(Debug)]
+pub struct ResourceTable<R> {
+    slots: Vec<Slot<R>>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl<R> Default for ResourceTable<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R> ResourceTable<R> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of objects in the table.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts an object and returns its handle.
+    ///
+    /// # Error
+    ///
+    /// If every handle is in use, then an error is returned.
+    pub fn insert(&mut self, value: R) -> Result<i32, ResourceError> {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                let index = self.slots.len() as u32;
+                if index > INDEX_MASK {
+                    return Err(ResourceError::Exhausted);
+                }
+                self.slots.push(Slot {
+                    generation: 1,
+                    value: None,
+                });
+                index
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        slot.value = Some(value);
+        self.len += 1;
+        Ok(((slot.generation << INDEX_BITS) | index) as i32)
+    }
+
+    /// Returns the object of a handle.
+    pub fn get(&self, handle: i32) -> Result<&R, ResourceError> {
+        let index = self.index_of(handle)?;
+        self.slots[index]
+            .value
+            .as_ref()
+            .ok_or(ResourceError::InvalidHandle(handle))
+    }
+
+    /// Returns the object of a handle mutably.
+    pub fn get_mut(&mut self, handle: i32) -> Result<&mut R, ResourceError> {
+        let index = self.index_of(handle)?;
+        self.slots[index]
+            .value
+            .as_mut()
+            .ok_or(ResourceError::InvalidHandle(handle))
+    }
+
+    /// Removes the object of a handle and returns it. The handle is invalid afterwards.
+    pub fn remove(&mut self, handle: i32) -> Result<R, ResourceError> {
+        let index = self.index_of(handle)?;
+        let slot = &mut self.slots[index];
+        let value = slot
+            .value
+            .take()
+            .ok_or(ResourceError::InvalidHandle(handle))?;
+        self.len -= 1;
+
+        // A slot whose generation is used up is retired rather than handing out old handles again.
+        if slot.generation < MAX_GENERATION {
+            slot.generation += 1;
+            self.free.push(index as u32);
+        }
+        Ok(value)
+    }
+
+    /// Returns `true` if the handle refers to an object in the table.
+    pub fn contains(&self, handle: i32) -> bool {
+        self.get(handle).is_ok()
+    }
+
+    /// Iterates over the handles and objects in the table.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, &R)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let handle = ((slot.generation << INDEX_BITS) | index as u32) as i32;
+            slot.value.as_ref().map(|value| (handle, value))
+        })
+    }
+
+    /// Removes every object from the table and invalidates their handles.
+    pub fn clear(&mut self) {
+        let handles = self.iter().map(|(handle, _)| handle).collect::<Vec<_>>();
+        for handle in handles {
+            let _ = self.remove(handle);
+        }
+    }
+
+    fn index_of(&self, handle: i32) -> Result<usize, ResourceError> {
+        if handle <= 0 {
+            return Err(ResourceError::InvalidHandle(handle));
+        }
+        let handle_bits = handle as u32;
+        let index = (handle_bits & INDEX_MASK) as usize;
+        let generation = handle_bits >> INDEX_BITS;
+        match self.slots.get(index) {
+            Some(slot) if slot.generation == generation => Ok(index),
+            _ => Err(ResourceError::InvalidHandle(handle)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    // Counts how many of its values have been dropped.
+    struct DropCounter(Rc<Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn rejects_stale_handles_after_reuse() {
+        let mut table = ResourceTable::new();
+        let old = table.insert("old").unwrap();
+        assert_eq!(table.remove(old), Ok("old"));
+        let new = table.insert("new").unwrap();
+
+        assert_ne!(old, new);
+        assert_eq!(old as u32 & INDEX_MASK, new as u32 & INDEX_MASK);
+        assert_eq!(table.get(old), Err(ResourceError::InvalidHandle(old)));
+        assert_eq!(table.remove(old), Err(ResourceError::InvalidHandle(old)));
+        assert_eq!(table.get(new), Ok(&"new"));
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn rejects_handles_not_above_zero() {
+        let mut table = ResourceTable::new();
+        table.insert(1).unwrap();
+        for handle in [0, -1, i32::MIN] {
+            assert_eq!(table.get(handle), Err(ResourceError::InvalidHandle(handle)));
+            assert_eq!(
+                table.get_mut(handle),
+                Err(ResourceError::InvalidHandle(handle))
+            );
+            assert_eq!(
+                table.remove(handle),
+                Err(ResourceError::InvalidHandle(handle))
+            );
+        }
+    }
+
+    #[test]
+    fn retires_slots_whose_generation_is_used_up() {
+        let mut table = ResourceTable::new();
+        let first = table.insert(0).unwrap();
+        table.remove(first).unwrap();
+        table.slots[0].generation = MAX_GENERATION;
+
+        let last = table.insert(1).unwrap();
+        assert!(last > 0);
+        assert_eq!(last as u32 >> INDEX_BITS, MAX_GENERATION);
+        table.remove(last).unwrap();
+
+        let next = table.insert(2).unwrap();
+        assert_eq!(next as u32 & INDEX_MASK, 1);
+        assert!(!table.contains(last));
+        assert_eq!(table.slots.len(), 2);
+    }
+
+    #[test]
+    fn hands_out_positive_handles_until_exhausted() {
+        let mut table = ResourceTable::new();
+        for _ in 0..=INDEX_MASK {
+            let handle = table.insert(()).unwrap();
+            assert!(handle > 0, "{handle}");
+        }
+        assert_eq!(table.len(), INDEX_MASK as usize + 1);
+        assert_eq!(table.insert(()), Err(ResourceError::Exhausted));
+
+        // A removed slot is free again.
+        let handle = table.iter().next().map(|(handle, _)| handle).unwrap();
+        table.remove(handle).unwrap();
+        assert!(table.insert(()).unwrap() > 0);
+    }
+
+    #[test]
+    fn clear_invalidates_handles() {
+        let mut table = ResourceTable::new();
+        let handles: Vec<_> = (0..3).map(|i| table.insert(i).unwrap()).collect();
+        table.clear();
+
+        assert!(table.is_empty());
+        for handle in handles {
+            assert!(!table.contains(handle));
+        }
+        let handle = table.insert(3).unwrap();
+        assert_eq!(table.get(handle), Ok(&3));
+    }
+
+    #[test]
+    fn drops_remaining_values_with_the_table() {
+        let drops = Rc::new(Cell::new(0));
+        let mut table = ResourceTable::new();
+        let handle = table.insert(DropCounter(drops.clone())).unwrap();
+        table.insert(DropCounter(drops.clone())).unwrap();
+        table.insert(DropCounter(drops.clone())).unwrap();
+
+        drop(table.remove(handle).unwrap());
+        assert_eq!(drops.get(), 1);
+        drop(table);
+        assert_eq!(drops.get(), 3);
+    }
+}
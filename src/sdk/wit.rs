@@ -0,0 +1,1711 @@
+//! Defines WIT interfaces for plugin modules, and lifts and lowers the values of their functions
+//! with the canonical ABI of the component model.
+//!
+//! Only the UTF-8 string encoding is supported, which is the one wit-bindgen guests use. A host
+//! function of an interface is registered with
+//! [PluginModule::add_wit_func](crate::module::PluginModule::add_wit_func), under the module name
+//! the guest imports the interface from.
+
+use std::fmt;
+
+use crate::core::instance::memory::Memory;
+use crate::core::types::{ValType, WasmVal};
+use crate::error::{CoreError, CoreExecutionError, ParseTypeError, WitError};
+use crate::sdk::module::SyncInstanceRef;
+
+const MAX_FLAT_PARAMS: usize = 16;
+const MAX_FLAT_RESULTS: usize = 1;
+// The most items a list of a zero-size type may have, as it isn't bounded by the memory.
+const MAX_ZERO_SIZE_LIST_LEN: u32 = 1 << 16;
+// The most flags a flags type may have, as they are lowered into a single i32.
+const MAX_FLAGS: usize = 32;
+const PAGE_SIZE: u64 = 64 * 1024;
+
+/// A type of a WIT interface, with every named type resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WitType {
+    Bool,
+    S8,
+    U8,
+    S16,
+    U16,
+    S32,
+    U32,
+    S64,
+    U64,
+    F32,
+    F64,
+    Char,
+    String,
+    List(Box<WitType>),
+    Tuple(Vec<WitType>),
+    Record(Vec<(String, WitType)>),
+    Variant(Vec<(String, Option<WitType>)>),
+    Enum(Vec<String>),
+    /// Up to 32 flags, see [WitType::flags]. Without any flag, it has no core type and takes no
+    /// memory.
+    Flags(Vec<String>),
+    Option(Box<WitType>),
+    Result {
+        ok: Option<Box<WitType>>,
+        err: Option<Box<WitType>>,
+    },
+}
+
+/// A value of a [WitType].
+#[derive(Debug, Clone, PartialEq)]
+pub enum WitValue {
+    Bool(bool),
+    S8(i8),
+    U8(u8),
+    S16(i16),
+    U16(u16),
+    S32(i32),
+    U32(u32),
+    S64(i64),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    String(String),
+    List(Vec<WitValue>),
+    Tuple(Vec<WitValue>),
+    Record(Vec<(String, WitValue)>),
+    Variant {
+        case: String,
+        payload: Option<Box<WitValue>>,
+    },
+    Enum(String),
+    /// The names of the flags which are set.
+    Flags(Vec<String>),
+    Option(Option<Box<WitValue>>),
+    Result(Result<Option<Box<WitValue>>, Option<Box<WitValue>>>),
+}
+
+/// A function of a WIT interface.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitFunc {
+    pub name: String,
+    pub params: Vec<(String, WitType)>,
+    pub result: Option<WitType>,
+}
+
+/// A WIT interface, e.g. `interface greeter { greet: func(name: string) -> string; }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitInterface {
+    pub name: String,
+    pub types: Vec<(String, WitType)>,
+    pub funcs: Vec<WitFunc>,
+}
+
+/// A host function of a WIT interface, which takes the lifted parameters and returns the result
+/// to lower.
+pub type WitHostFn<T> = for<'a> fn(
+    &'a mut SyncInstanceRef,
+    &'a mut T,
+    Vec<WitValue>,
+) -> Result<Option<WitValue>, CoreError>;
+
+fn align_to(n: u32, align: u32) -> u32 {
+    n.div_ceil(align) * align
+}
+
+fn discriminant_size(cases: usize) -> u32 {
+    if cases <= 1 << 8 {
+        1
+    } else if cases <= 1 << 16 {
+        2
+    } else {
+        4
+    }
+}
+
+fn flags_size(flags: usize) -> u32 {
+    match flags {
+        0 => 0,
+        1..=8 => 1,
+        9..=16 => 2,
+        _ => 4,
+    }
+}
+
+fn join(a: ValType, b: ValType) -> ValType {
+    match (a, b) {
+        _ if a == b => a,
+        (ValType::I32, ValType::F32) | (ValType::F32, ValType::I32) => ValType::I32,
+        _ => ValType::I64,
+    }
+}
+
+impl WitType {
+    /// Creates a flags type, which has at most 32 flags.
+    ///
+    /// # Error
+    ///
+    /// If there are more than 32 flags, then an error is returned. A [WitType::Flags] with more
+    /// flags fails to lift and lower.
+    pub fn flags(names: Vec<String>) -> Result<Self, WitError> {
+        check_flags(&names)?;
+        Ok(WitType::Flags(names))
+    }
+
+    /// Returns the payload types of the cases of a variant, an enum, an option or a result.
+    fn cases(&self) -> Option<Vec<Option<&WitType>>> {
+        match self {
+            WitType::Variant(cases) => Some(cases.iter().map(|(_, ty)| ty.as_ref()).collect()),
+            WitType::Enum(names) => Some(vec![None; names.len()]),
+            WitType::Option(ty) => Some(vec![None, Some(ty)]),
+            WitType::Result { ok, err } => Some(vec![ok.as_deref(), err.as_deref()]),
+            _ => None,
+        }
+    }
+
+    fn max_case_alignment(cases: &[Option<&WitType>]) -> u32 {
+        cases
+            .iter()
+            .flatten()
+            .map(|ty| ty.alignment())
+            .max()
+            .unwrap_or(1)
+    }
+
+    // The offset of the payload of a variant from its discriminant.
+    fn payload_offset(cases: &[Option<&WitType>]) -> u32 {
+        align_to(
+            discriminant_size(cases.len()),
+            Self::max_case_alignment(cases),
+        )
+    }
+
+    /// Returns the alignment of the type in linear memory.
+    pub fn alignment(&self) -> u32 {
+        match self {
+            WitType::Bool | WitType::S8 | WitType::U8 => 1,
+            WitType::S16 | WitType::U16 => 2,
+            WitType::S32 | WitType::U32 | WitType::F32 | WitType::Char => 4,
+            WitType::S64 | WitType::U64 | WitType::F64 => 8,
+            WitType::String | WitType::List(_) => 4,
+            WitType::Tuple(tys) => tys.iter().map(Self::alignment).max().unwrap_or(1),
+            WitType::Record(fields) => fields
+                .iter()
+                .map(|(_, ty)| ty.alignment())
+                .max()
+                .unwrap_or(1),
+            WitType::Flags(names) => flags_size(names.len()).max(1),
+            _ => {
+                let cases = self.cases().unwrap_or_default();
+                discriminant_size(cases.len()).max(Self::max_case_alignment(&cases))
+            }
+        }
+    }
+
+    /// Returns the size of the type in linear memory.
+    pub fn size(&self) -> u32 {
+        match self {
+            WitType::Bool | WitType::S8 | WitType::U8 => 1,
+            WitType::S16 | WitType::U16 => 2,
+            WitType::S32 | WitType::U32 | WitType::F32 | WitType::Char => 4,
+            WitType::S64 | WitType::U64 | WitType::F64 => 8,
+            WitType::String | WitType::List(_) => 8,
+            WitType::Tuple(tys) => self.fields_size(tys.iter()),
+            WitType::Record(fields) => self.fields_size(fields.iter().map(|(_, ty)| ty)),
+            WitType::Flags(names) => flags_size(names.len()),
+            _ => {
+                let cases = self.cases().unwrap_or_default();
+                let payload = cases.iter().flatten().map(|ty| ty.size()).max();
+                let size = Self::payload_offset(&cases) + payload.unwrap_or(0);
+                align_to(size, self.alignment())
+            }
+        }
+    }
+
+    fn fields_size<'t>(&self, tys: impl Iterator<Item = &'t WitType>) -> u32 {
+        let mut size = 0;
+        for ty in tys {
+            size = align_to(size, ty.alignment()) + ty.size();
+        }
+        align_to(size, self.alignment())
+    }
+
+    /// Appends the core types the type is flattened into.
+    pub fn flatten(&self, out: &mut Vec<ValType>) {
+        match self {
+            WitType::Bool
+            | WitType::S8
+            | WitType::U8
+            | WitType::S16
+            | WitType::U16
+            | WitType::S32
+            | WitType::U32
+            | WitType::Char => out.push(ValType::I32),
+            WitType::Flags(names) if names.is_empty() => {}
+            WitType::Flags(_) => out.push(ValType::I32),
+            WitType::S64 | WitType::U64 => out.push(ValType::I64),
+            WitType::F32 => out.push(ValType::F32),
+            WitType::F64 => out.push(ValType::F64),
+            WitType::String | WitType::List(_) => out.extend([ValType::I32, ValType::I32]),
+            WitType::Tuple(tys) => tys.iter().for_each(|ty| ty.flatten(out)),
+            WitType::Record(fields) => fields.iter().for_each(|(_, ty)| ty.flatten(out)),
+            _ => {
+                out.push(ValType::I32);
+                out.extend(self.joined_payload());
+            }
+        }
+    }
+
+    fn flat(&self) -> Vec<ValType> {
+        let mut out = Vec::new();
+        self.flatten(&mut out);
+        out
+    }
+
+    // The core types every case payload of a variant-like type is flattened into.
+    fn joined_payload(&self) -> Vec<ValType> {
+        let mut joined: Vec<ValType> = Vec::new();
+        for ty in self.cases().unwrap_or_default().into_iter().flatten() {
+            for (i, flat) in ty.flat().into_iter().enumerate() {
+                match joined.get_mut(i) {
+                    Some(j) => *j = join(*j, flat),
+                    None => joined.push(flat),
+                }
+            }
+        }
+        joined
+    }
+
+    fn mismatch(&self) -> WitError {
+        WitError::TypeMismatch(self.to_string())
+    }
+
+    fn make_case(&self, case: usize, payload: Option<WitValue>) -> WitValue {
+        let payload = payload.map(Box::new);
+        match self {
+            WitType::Variant(cases) => WitValue::Variant {
+                case: cases[case].0.clone(),
+                payload,
+            },
+            WitType::Enum(names) => WitValue::Enum(names[case].clone()),
+            WitType::Option(_) if case == 0 => WitValue::Option(None),
+            WitType::Option(_) => WitValue::Option(payload),
+            WitType::Result { .. } if case == 0 => WitValue::Result(Ok(payload)),
+            _ => WitValue::Result(Err(payload)),
+        }
+    }
+
+    // Returns the case of a variant-like value and its payload, checked against this type.
+    fn case_of<'v>(&self, value: &'v WitValue) -> Result<(usize, Option<&'v WitValue>), WitError> {
+        let cases = self.cases().ok_or_else(|| self.mismatch())?;
+        let (case, payload) = match (self, value) {
+            (WitType::Variant(types), WitValue::Variant { case, payload }) => {
+                let idx = types.iter().position(|(name, _)| name == case);
+                (idx.ok_or_else(|| self.mismatch())?, payload.as_deref())
+            }
+            (WitType::Enum(names), WitValue::Enum(name)) => {
+                let idx = names.iter().position(|n| n == name);
+                (idx.ok_or_else(|| self.mismatch())?, None)
+            }
+            (WitType::Option(_), WitValue::Option(v)) => match v {
+                None => (0, None),
+                Some(v) => (1, Some(&**v)),
+            },
+            (WitType::Result { .. }, WitValue::Result(v)) => match v {
+                Ok(v) => (0, v.as_deref()),
+                Err(v) => (1, v.as_deref()),
+            },
+            _ => return Err(self.mismatch()),
+        };
+        if cases[case].is_some() != payload.is_some() {
+            return Err(self.mismatch());
+        }
+        Ok((case, payload))
+    }
+
+    fn flags_bits(&self, names: &[String], set: &[String]) -> Result<u64, WitError> {
+        check_flags(names)?;
+        set.iter().try_fold(0u64, |bits, flag| {
+            let idx = names
+                .iter()
+                .position(|n| n == flag)
+                .ok_or_else(|| self.mismatch())?;
+            Ok(bits | 1 << idx)
+        })
+    }
+}
+
+fn check_flags(names: &[String]) -> Result<(), WitError> {
+    if names.len() > MAX_FLAGS {
+        return Err(WitError::TooManyFlags(names.len()));
+    }
+    Ok(())
+}
+
+fn flags_from_bits(names: &[String], bits: u64) -> Result<WitValue, WitError> {
+    check_flags(names)?;
+    Ok(WitValue::Flags(
+        names
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| bits & (1 << i) != 0)
+            .map(|(_, name)| name.clone())
+            .collect(),
+    ))
+}
+
+fn to_char(code: u32) -> Result<char, WitError> {
+    char::from_u32(code).ok_or(WitError::InvalidChar(code))
+}
+
+fn out_of_bounds() -> WitError {
+    WitError::Memory(CoreError::Execution(CoreExecutionError::MemoryOutOfBounds))
+}
+
+fn offset(ptr: u32, off: u32) -> Result<u32, WitError> {
+    ptr.checked_add(off).ok_or_else(out_of_bounds)
+}
+
+struct FlatReader {
+    vals: std::vec::IntoIter<WasmVal>,
+}
+
+impl FlatReader {
+    fn new(vals: Vec<WasmVal>) -> Self {
+        Self {
+            vals: vals.into_iter(),
+        }
+    }
+
+    fn next(&mut self) -> Result<WasmVal, WitError> {
+        self.vals.next().ok_or(WitError::MissingValue)
+    }
+
+    fn i32(&mut self) -> Result<i32, WitError> {
+        match self.next()? {
+            WasmVal::I32(v) => Ok(v),
+            _ => Err(WitError::TypeMismatch(ValType::I32.to_string())),
+        }
+    }
+
+    fn i64(&mut self) -> Result<i64, WitError> {
+        match self.next()? {
+            WasmVal::I64(v) => Ok(v),
+            _ => Err(WitError::TypeMismatch(ValType::I64.to_string())),
+        }
+    }
+
+    fn f32(&mut self) -> Result<f32, WitError> {
+        match self.next()? {
+            WasmVal::F32(v) => Ok(v),
+            _ => Err(WitError::TypeMismatch(ValType::F32.to_string())),
+        }
+    }
+
+    fn f64(&mut self) -> Result<f64, WitError> {
+        match self.next()? {
+            WasmVal::F64(v) => Ok(v),
+            _ => Err(WitError::TypeMismatch(ValType::F64.to_string())),
+        }
+    }
+}
+
+// Converts a core value from the joined type of a variant payload back to the type of the case.
+fn from_joined(val: WasmVal, ty: ValType) -> Result<WasmVal, WitError> {
+    let val = match (val, ty) {
+        (WasmVal::I32(v), ValType::F32) => WasmVal::F32(f32::from_bits(v as u32)),
+        (WasmVal::I64(v), ValType::I32) => WasmVal::I32(v as i32),
+        (WasmVal::I64(v), ValType::F32) => WasmVal::F32(f32::from_bits(v as u32)),
+        (WasmVal::I64(v), ValType::F64) => WasmVal::F64(f64::from_bits(v as u64)),
+        (val, _) if val.ty() == ty => val,
+        _ => return Err(WitError::TypeMismatch(ty.to_string())),
+    };
+    Ok(val)
+}
+
+// Converts a core value of a variant payload to the joined type of all the cases.
+fn to_joined(val: &WasmVal, ty: ValType) -> WasmVal {
+    match (val, ty) {
+        (WasmVal::F32(v), ValType::I32) => WasmVal::I32(v.to_bits() as i32),
+        (WasmVal::I32(v), ValType::I64) => WasmVal::I64(*v as u32 as i64),
+        (WasmVal::F32(v), ValType::I64) => WasmVal::I64(v.to_bits() as i64),
+        (WasmVal::F64(v), ValType::I64) => WasmVal::I64(v.to_bits() as i64),
+        (val, _) => val.clone(),
+    }
+}
+
+/// Lifts values from the flat core values and the linear memory of the guest.
+struct Lifter<'m> {
+    mem: &'m Memory,
+}
+
+impl Lifter<'_> {
+    // Checks that `size` bytes at `ptr` are in the memory, before anything is read or allocated.
+    fn check_range(&self, ptr: u32, size: u64) -> Result<(), WitError> {
+        let mem_size = u64::from(self.mem.page_size()) * PAGE_SIZE;
+        match u64::from(ptr).checked_add(size) {
+            Some(end) if end <= mem_size => Ok(()),
+            _ => Err(out_of_bounds()),
+        }
+    }
+
+    fn load_uint(&self, ptr: u32, size: u32) -> Result<u64, WitError> {
+        let bytes = self.mem.read_bytes(ptr, size).map_err(WitError::Memory)?;
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(&bytes);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn load(&self, ty: &WitType, ptr: u32) -> Result<WitValue, WitError> {
+        if !ptr.is_multiple_of(ty.alignment()) {
+            return Err(WitError::Unaligned(ptr));
+        }
+        let v = match ty {
+            WitType::Bool => WitValue::Bool(self.load_uint(ptr, 1)? != 0),
+            WitType::S8 => WitValue::S8(self.load_uint(ptr, 1)? as i8),
+            WitType::U8 => WitValue::U8(self.load_uint(ptr, 1)? as u8),
+            WitType::S16 => WitValue::S16(self.load_uint(ptr, 2)? as i16),
+            WitType::U16 => WitValue::U16(self.load_uint(ptr, 2)? as u16),
+            WitType::S32 => WitValue::S32(self.load_uint(ptr, 4)? as i32),
+            WitType::U32 => WitValue::U32(self.load_uint(ptr, 4)? as u32),
+            WitType::S64 => WitValue::S64(self.load_uint(ptr, 8)? as i64),
+            WitType::U64 => WitValue::U64(self.load_uint(ptr, 8)?),
+            WitType::F32 => WitValue::F32(f32::from_bits(self.load_uint(ptr, 4)? as u32)),
+            WitType::F64 => WitValue::F64(f64::from_bits(self.load_uint(ptr, 8)?)),
+            WitType::Char => WitValue::Char(to_char(self.load_uint(ptr, 4)? as u32)?),
+            WitType::String | WitType::List(_) => {
+                let data = self.load_uint(ptr, 4)? as u32;
+                let len = self.load_uint(offset(ptr, 4)?, 4)? as u32;
+                self.load_buffer(ty, data, len)?
+            }
+            WitType::Tuple(tys) => WitValue::Tuple(self.load_fields(tys.iter(), ptr)?),
+            WitType::Record(fields) => {
+                let vals = self.load_fields(fields.iter().map(|(_, ty)| ty), ptr)?;
+                let names = fields.iter().map(|(name, _)| name.clone());
+                WitValue::Record(names.zip(vals).collect())
+            }
+            WitType::Flags(names) => {
+                flags_from_bits(names, self.load_uint(ptr, flags_size(names.len()))?)?
+            }
+            _ => {
+                let cases = ty.cases().unwrap_or_default();
+                let case = self.load_uint(ptr, discriminant_size(cases.len()))? as u32;
+                let payload_ty = cases
+                    .get(case as usize)
+                    .ok_or(WitError::InvalidDiscriminant(case))?;
+                let payload = match payload_ty {
+                    Some(payload_ty) => {
+                        let payload_ptr = offset(ptr, WitType::payload_offset(&cases))?;
+                        Some(self.load(payload_ty, payload_ptr)?)
+                    }
+                    None => None,
+                };
+                ty.make_case(case as usize, payload)
+            }
+        };
+        Ok(v)
+    }
+
+    fn load_fields<'t>(
+        &self,
+        tys: impl Iterator<Item = &'t WitType>,
+        ptr: u32,
+    ) -> Result<Vec<WitValue>, WitError> {
+        let mut off = 0;
+        let mut vals = Vec::new();
+        for ty in tys {
+            off = align_to(off, ty.alignment());
+            vals.push(self.load(ty, offset(ptr, off)?)?);
+            off += ty.size();
+        }
+        Ok(vals)
+    }
+
+    // Loads the string or the list of `len` items at `ptr`.
+    fn load_buffer(&self, ty: &WitType, ptr: u32, len: u32) -> Result<WitValue, WitError> {
+        match ty {
+            WitType::String => {
+                self.check_range(ptr, u64::from(len))?;
+                let bytes = self.mem.read_bytes(ptr, len).map_err(WitError::Memory)?;
+                String::from_utf8(bytes)
+                    .map(WitValue::String)
+                    .map_err(|_| WitError::InvalidUtf8)
+            }
+            WitType::List(elem) => {
+                if !ptr.is_multiple_of(elem.alignment()) {
+                    return Err(WitError::Unaligned(ptr));
+                }
+                let size = elem.size();
+                if size == 0 && len > MAX_ZERO_SIZE_LIST_LEN {
+                    return Err(WitError::ListTooLong(len));
+                }
+                self.check_range(ptr, u64::from(len) * u64::from(size))?;
+                (0..len)
+                    .map(|i| self.load(elem, ptr + i * size))
+                    .collect::<Result<_, _>>()
+                    .map(WitValue::List)
+            }
+            _ => Err(ty.mismatch()),
+        }
+    }
+
+    fn lift_flat(&self, ty: &WitType, vals: &mut FlatReader) -> Result<WitValue, WitError> {
+        let v = match ty {
+            WitType::Bool => WitValue::Bool(vals.i32()? != 0),
+            WitType::S8 => WitValue::S8(vals.i32()? as i8),
+            WitType::U8 => WitValue::U8(vals.i32()? as u8),
+            WitType::S16 => WitValue::S16(vals.i32()? as i16),
+            WitType::U16 => WitValue::U16(vals.i32()? as u16),
+            WitType::S32 => WitValue::S32(vals.i32()?),
+            WitType::U32 => WitValue::U32(vals.i32()? as u32),
+            WitType::S64 => WitValue::S64(vals.i64()?),
+            WitType::U64 => WitValue::U64(vals.i64()? as u64),
+            WitType::F32 => WitValue::F32(vals.f32()?),
+            WitType::F64 => WitValue::F64(vals.f64()?),
+            WitType::Char => WitValue::Char(to_char(vals.i32()? as u32)?),
+            WitType::Flags(names) if names.is_empty() => WitValue::Flags(vec![]),
+            WitType::Flags(names) => flags_from_bits(names, vals.i32()? as u32 as u64)?,
+            WitType::String | WitType::List(_) => {
+                let ptr = vals.i32()? as u32;
+                let len = vals.i32()? as u32;
+                self.load_buffer(ty, ptr, len)?
+            }
+            WitType::Tuple(tys) => WitValue::Tuple(
+                tys.iter()
+                    .map(|ty| self.lift_flat(ty, vals))
+                    .collect::<Result<_, _>>()?,
+            ),
+            WitType::Record(fields) => WitValue::Record(
+                fields
+                    .iter()
+                    .map(|(name, ty)| Ok((name.clone(), self.lift_flat(ty, vals)?)))
+                    .collect::<Result<_, WitError>>()?,
+            ),
+            _ => {
+                let case = vals.i32()? as u32;
+                let joined = (0..ty.joined_payload().len())
+                    .map(|_| vals.next())
+                    .collect::<Result<Vec<_>, _>>()?;
+                let cases = ty.cases().unwrap_or_default();
+                let payload_ty = cases
+                    .get(case as usize)
+                    .ok_or(WitError::InvalidDiscriminant(case))?;
+                let payload = match payload_ty {
+                    Some(payload_ty) => {
+                        let flat = payload_ty
+                            .flat()
+                            .into_iter()
+                            .zip(joined)
+                            .map(|(flat, val)| from_joined(val, flat))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Some(self.lift_flat(payload_ty, &mut FlatReader::new(flat))?)
+                    }
+                    None => None,
+                };
+                ty.make_case(case as usize, payload)
+            }
+        };
+        Ok(v)
+    }
+}
+
+/// Allocates the guest memory strings and lists are lowered into.
+trait GuestAlloc {
+    fn alloc(&mut self, size: u32, align: u32) -> Result<u32, WitError>;
+}
+
+/// Allocates with the `cabi_realloc` export of the guest.
+impl GuestAlloc for SyncInstanceRef {
+    fn alloc(&mut self, size: u32, align: u32) -> Result<u32, WitError> {
+        let args = vec![
+            WasmVal::I32(0),
+            WasmVal::I32(0),
+            WasmVal::I32(align as i32),
+            WasmVal::I32(size as i32),
+        ];
+        let ret = self.call("cabi_realloc", args).map_err(WitError::Realloc)?;
+        match ret.as_slice() {
+            [WasmVal::I32(ptr)] => Ok(*ptr as u32),
+            _ => Err(WitError::Realloc(CoreError::runtime())),
+        }
+    }
+}
+
+/// Lowers values into flat core values and into the linear memory of the guest.
+struct Lowerer<'i> {
+    alloc: &'i mut dyn GuestAlloc,
+    mem: &'i mut Memory,
+}
+
+impl Lowerer<'_> {
+    fn realloc(&mut self, size: u32, align: u32) -> Result<u32, WitError> {
+        // Any aligned address is valid for nothing.
+        if size == 0 {
+            return Ok(align);
+        }
+        let ptr = self.alloc.alloc(size, align)?;
+        if !ptr.is_multiple_of(align) {
+            return Err(WitError::Unaligned(ptr));
+        }
+        Ok(ptr)
+    }
+
+    fn store_uint(&mut self, ptr: u32, size: u32, v: u64) -> Result<(), WitError> {
+        self.mem
+            .write_bytes(&v.to_le_bytes()[..size as usize], ptr)
+            .map_err(WitError::Memory)
+    }
+
+    // Copies a string or a list into newly allocated guest memory and returns its address and
+    // length.
+    fn store_buffer(&mut self, ty: &WitType, v: &WitValue) -> Result<(u32, u32), WitError> {
+        match (ty, v) {
+            (WitType::String, WitValue::String(s)) => {
+                let len = u32::try_from(s.len()).map_err(|_| ty.mismatch())?;
+                let ptr = self.realloc(len, 1)?;
+                self.mem
+                    .write_bytes(s.as_bytes(), ptr)
+                    .map_err(WitError::Memory)?;
+                Ok((ptr, len))
+            }
+            (WitType::List(elem), WitValue::List(items)) => {
+                let len = u32::try_from(items.len()).map_err(|_| ty.mismatch())?;
+                let size = elem.size().checked_mul(len).ok_or_else(|| ty.mismatch())?;
+                let ptr = self.realloc(size, elem.alignment())?;
+                for (i, item) in items.iter().enumerate() {
+                    self.store(elem, item, offset(ptr, i as u32 * elem.size())?)?;
+                }
+                Ok((ptr, len))
+            }
+            _ => Err(ty.mismatch()),
+        }
+    }
+
+    fn store(&mut self, ty: &WitType, v: &WitValue, ptr: u32) -> Result<(), WitError> {
+        match (ty, v) {
+            (WitType::Bool, WitValue::Bool(v)) => self.store_uint(ptr, 1, *v as u64),
+            (WitType::S8, WitValue::S8(v)) => self.store_uint(ptr, 1, *v as u8 as u64),
+            (WitType::U8, WitValue::U8(v)) => self.store_uint(ptr, 1, *v as u64),
+            (WitType::S16, WitValue::S16(v)) => self.store_uint(ptr, 2, *v as u16 as u64),
+            (WitType::U16, WitValue::U16(v)) => self.store_uint(ptr, 2, *v as u64),
+            (WitType::S32, WitValue::S32(v)) => self.store_uint(ptr, 4, *v as u32 as u64),
+            (WitType::U32, WitValue::U32(v)) => self.store_uint(ptr, 4, *v as u64),
+            (WitType::S64, WitValue::S64(v)) => self.store_uint(ptr, 8, *v as u64),
+            (WitType::U64, WitValue::U64(v)) => self.store_uint(ptr, 8, *v),
+            (WitType::F32, WitValue::F32(v)) => self.store_uint(ptr, 4, v.to_bits() as u64),
+            (WitType::F64, WitValue::F64(v)) => self.store_uint(ptr, 8, v.to_bits()),
+            (WitType::Char, WitValue::Char(v)) => self.store_uint(ptr, 4, *v as u64),
+            (WitType::String, _) | (WitType::List(_), _) => {
+                let (data, len) = self.store_buffer(ty, v)?;
+                self.store_uint(ptr, 4, data as u64)?;
+                self.store_uint(offset(ptr, 4)?, 4, len as u64)
+            }
+            (WitType::Tuple(tys), WitValue::Tuple(vals)) if tys.len() == vals.len() => {
+                self.store_fields(tys.iter().zip(vals), ptr)
+            }
+            (WitType::Record(fields), WitValue::Record(_)) => {
+                let vals = record_values(ty, fields, v)?;
+                self.store_fields(fields.iter().map(|(_, ty)| ty).zip(vals), ptr)
+            }
+            (WitType::Flags(names), WitValue::Flags(set)) => {
+                let bits = ty.flags_bits(names, set)?;
+                self.store_uint(ptr, flags_size(names.len()), bits)
+            }
+            _ => {
+                let (case, payload) = ty.case_of(v)?;
+                let cases = ty.cases().unwrap_or_default();
+                self.store_uint(ptr, discriminant_size(cases.len()), case as u64)?;
+                match (cases[case], payload) {
+                    (Some(payload_ty), Some(payload)) => {
+                        let payload_ptr = offset(ptr, WitType::payload_offset(&cases))?;
+                        self.store(payload_ty, payload, payload_ptr)
+                    }
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    fn store_fields<'t>(
+        &mut self,
+        fields: impl Iterator<Item = (&'t WitType, &'t WitValue)>,
+        ptr: u32,
+    ) -> Result<(), WitError> {
+        let mut off = 0;
+        for (ty, v) in fields {
+            off = align_to(off, ty.alignment());
+            self.store(ty, v, offset(ptr, off)?)?;
+            off += ty.size();
+        }
+        Ok(())
+    }
+
+    fn lower_flat(
+        &mut self,
+        ty: &WitType,
+        v: &WitValue,
+        out: &mut Vec<WasmVal>,
+    ) -> Result<(), WitError> {
+        match (ty, v) {
+            (WitType::Bool, WitValue::Bool(v)) => out.push(WasmVal::I32(*v as i32)),
+            (WitType::S8, WitValue::S8(v)) => out.push(WasmVal::I32(*v as i32)),
+            (WitType::U8, WitValue::U8(v)) => out.push(WasmVal::I32(*v as i32)),
+            (WitType::S16, WitValue::S16(v)) => out.push(WasmVal::I32(*v as i32)),
+            (WitType::U16, WitValue::U16(v)) => out.push(WasmVal::I32(*v as i32)),
+            (WitType::S32, WitValue::S32(v)) => out.push(WasmVal::I32(*v)),
+            (WitType::U32, WitValue::U32(v)) => out.push(WasmVal::I32(*v as i32)),
+            (WitType::S64, WitValue::S64(v)) => out.push(WasmVal::I64(*v)),
+            (WitType::U64, WitValue::U64(v)) => out.push(WasmVal::I64(*v as i64)),
+            (WitType::F32, WitValue::F32(v)) => out.push(WasmVal::F32(*v)),
+            (WitType::F64, WitValue::F64(v)) => out.push(WasmVal::F64(*v)),
+            (WitType::Char, WitValue::Char(v)) => out.push(WasmVal::I32(*v as i32)),
+            (WitType::Flags(names), WitValue::Flags(set)) => {
+                let bits = ty.flags_bits(names, set)?;
+                if !names.is_empty() {
+                    out.push(WasmVal::I32(bits as i32));
+                }
+            }
+            (WitType::String, _) | (WitType::List(_), _) => {
+                let (ptr, len) = self.store_buffer(ty, v)?;
+                out.extend([WasmVal::I32(ptr as i32), WasmVal::I32(len as i32)]);
+            }
+            (WitType::Tuple(tys), WitValue::Tuple(vals)) if tys.len() == vals.len() => {
+                for (ty, v) in tys.iter().zip(vals) {
+                    self.lower_flat(ty, v, out)?;
+                }
+            }
+            (WitType::Record(fields), WitValue::Record(_)) => {
+                let vals = record_values(ty, fields, v)?;
+                for ((_, ty), v) in fields.iter().zip(vals) {
+                    self.lower_flat(ty, v, out)?;
+                }
+            }
+            _ => {
+                let (case, payload) = ty.case_of(v)?;
+                let cases = ty.cases().unwrap_or_default();
+                let mut flat = Vec::new();
+                if let (Some(payload_ty), Some(payload)) = (cases[case], payload) {
+                    self.lower_flat(payload_ty, payload, &mut flat)?;
+                }
+                out.push(WasmVal::I32(case as i32));
+                for (i, joined) in ty.joined_payload().into_iter().enumerate() {
+                    out.push(match flat.get(i) {
+                        Some(v) => to_joined(v, joined),
+                        None => WasmVal::default_for(joined),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Returns the values of a record in the order of the fields of its type.
+fn record_values<'v>(
+    ty: &WitType,
+    fields: &[(String, WitType)],
+    v: &'v WitValue,
+) -> Result<Vec<&'v WitValue>, WitError> {
+    let WitValue::Record(vals) = v else {
+        return Err(ty.mismatch());
+    };
+    if vals.len() != fields.len() {
+        return Err(ty.mismatch());
+    }
+    fields
+        .iter()
+        .map(|(name, _)| {
+            vals.iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v)
+                .ok_or_else(|| ty.mismatch())
+        })
+        .collect()
+}
+
+impl WitFunc {
+    /// Returns the core function type the guest imports the function with.
+    ///
+    /// Parameters flattened into more than 16 core values are passed by a pointer, and a result
+    /// flattened into more than one core value is stored through a pointer the guest appends to
+    /// the parameters.
+    pub fn core_type(&self) -> (Vec<ValType>, Vec<ValType>) {
+        let mut params = Vec::new();
+        for (_, ty) in &self.params {
+            ty.flatten(&mut params);
+        }
+        if params.len() > MAX_FLAT_PARAMS {
+            params = vec![ValType::I32];
+        }
+        let mut results = self.result.as_ref().map(WitType::flat).unwrap_or_default();
+        if results.len() > MAX_FLAT_RESULTS {
+            params.push(ValType::I32);
+            results.clear();
+        }
+        (params, results)
+    }
+
+    // Lifts the arguments, calls the handler and lowers its result.
+    pub(crate) fn call_host<T>(
+        &self,
+        handler: WitHostFn<T>,
+        inst: &mut SyncInstanceRef,
+        mem: &mut Memory,
+        data: &mut T,
+        mut args: Vec<WasmVal>,
+    ) -> Result<Vec<WasmVal>, WitError> {
+        let mut flat_params = Vec::new();
+        for (_, ty) in &self.params {
+            ty.flatten(&mut flat_params);
+        }
+        let result_flat = self.result.as_ref().map(WitType::flat).unwrap_or_default();
+        let retptr = if result_flat.len() > MAX_FLAT_RESULTS {
+            match args.pop() {
+                Some(WasmVal::I32(ptr)) => Some(ptr as u32),
+                _ => return Err(WitError::MissingValue),
+            }
+        } else {
+            None
+        };
+
+        let lifter = Lifter { mem: &*mem };
+        let mut reader = FlatReader::new(args);
+        let params = if flat_params.len() > MAX_FLAT_PARAMS {
+            let ptr = reader.i32()? as u32;
+            let tys = self.params.iter().map(|(_, ty)| ty);
+            let tuple = WitType::Tuple(tys.clone().cloned().collect());
+            if !ptr.is_multiple_of(tuple.alignment()) {
+                return Err(WitError::Unaligned(ptr));
+            }
+            lifter.load_fields(tys, ptr)?
+        } else {
+            self.params
+                .iter()
+                .map(|(_, ty)| lifter.lift_flat(ty, &mut reader))
+                .collect::<Result<_, _>>()?
+        };
+
+        let result = handler(inst, data, params).map_err(WitError::Host)?;
+        let mut lowerer = Lowerer { alloc: inst, mem };
+        match (&self.result, result) {
+            (None, None) => Ok(vec![]),
+            (Some(ty), Some(v)) => match retptr {
+                Some(retptr) => {
+                    if !retptr.is_multiple_of(ty.alignment()) {
+                        return Err(WitError::Unaligned(retptr));
+                    }
+                    lowerer.store(ty, &v, retptr)?;
+                    Ok(vec![])
+                }
+                None => {
+                    let mut out = Vec::new();
+                    lowerer.lower_flat(ty, &v, &mut out)?;
+                    Ok(out)
+                }
+            },
+            (Some(ty), None) => Err(ty.mismatch()),
+            (None, Some(_)) => Err(WitError::TypeMismatch("no result".to_string())),
+        }
+    }
+}
+
+impl WitInterface {
+    /// Returns the function of the given name.
+    pub fn func(&self, name: &str) -> Option<&WitFunc> {
+        self.funcs.iter().find(|f| f.name == name)
+    }
+
+    /// Parses an interface from WIT.
+    ///
+    /// Records, variants, enums, flags, type aliases and functions are supported. Resources,
+    /// `use` and worlds are not.
+    pub fn parse(src: &str) -> Result<Self, ParseTypeError> {
+        Parser::new(src)?.interface()
+    }
+}
+
+fn write_list<T>(
+    f: &mut fmt::Formatter<'_>,
+    items: &[T],
+    mut write: impl FnMut(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write(f, item)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for WitType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WitType::Bool => write!(f, "bool"),
+            WitType::S8 => write!(f, "s8"),
+            WitType::U8 => write!(f, "u8"),
+            WitType::S16 => write!(f, "s16"),
+            WitType::U16 => write!(f, "u16"),
+            WitType::S32 => write!(f, "s32"),
+            WitType::U32 => write!(f, "u32"),
+            WitType::S64 => write!(f, "s64"),
+            WitType::U64 => write!(f, "u64"),
+            WitType::F32 => write!(f, "f32"),
+            WitType::F64 => write!(f, "f64"),
+            WitType::Char => write!(f, "char"),
+            WitType::String => write!(f, "string"),
+            WitType::List(ty) => write!(f, "list<{ty}>"),
+            WitType::Option(ty) => write!(f, "option<{ty}>"),
+            WitType::Tuple(tys) => {
+                write!(f, "tuple<")?;
+                write_list(f, tys, |f, ty| write!(f, "{ty}"))?;
+                write!(f, ">")
+            }
+            WitType::Record(fields) => {
+                write!(f, "record {{ ")?;
+                write_list(f, fields, |f, (name, ty)| write!(f, "{name}: {ty}"))?;
+                write!(f, " }}")
+            }
+            WitType::Variant(cases) => {
+                write!(f, "variant {{ ")?;
+                write_list(f, cases, |f, (name, ty)| match ty {
+                    Some(ty) => write!(f, "{name}({ty})"),
+                    None => write!(f, "{name}"),
+                })?;
+                write!(f, " }}")
+            }
+            WitType::Enum(names) => {
+                write!(f, "enum {{ ")?;
+                write_list(f, names, |f, name| write!(f, "{name}"))?;
+                write!(f, " }}")
+            }
+            WitType::Flags(names) => {
+                write!(f, "flags {{ ")?;
+                write_list(f, names, |f, name| write!(f, "{name}"))?;
+                write!(f, " }}")
+            }
+            WitType::Result { ok, err } => match (ok, err) {
+                (None, None) => write!(f, "result"),
+                (Some(ok), None) => write!(f, "result<{ok}>"),
+                (None, Some(err)) => write!(f, "result<_, {err}>"),
+                (Some(ok), Some(err)) => write!(f, "result<{ok}, {err}>"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for WitFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: func(", self.name)?;
+        write_list(f, &self.params, |f, (name, ty)| write!(f, "{name}: {ty}"))?;
+        write!(f, ")")?;
+        match &self.result {
+            Some(ty) => write!(f, " -> {ty}"),
+            None => Ok(()),
+        }
+    }
+}
+
+// A type as written in WIT, before the named types are resolved.
+#[derive(Debug, Clone)]
+enum TypeRef {
+    Resolved(WitType),
+    Named(String),
+    List(Box<TypeRef>),
+    Option(Box<TypeRef>),
+    Tuple(Vec<TypeRef>),
+    Result(Option<Box<TypeRef>>, Option<Box<TypeRef>>),
+}
+
+#[derive(Debug, Clone)]
+enum TypeDef {
+    Alias(TypeRef),
+    Record(Vec<(String, TypeRef)>),
+    Variant(Vec<(String, Option<TypeRef>)>),
+    Resolved(WitType),
+}
+
+struct Parser<'s> {
+    src: &'s str,
+    tokens: Vec<&'s str>,
+    pos: usize,
+    defs: Vec<(String, TypeDef)>,
+}
+
+fn tokenize(src: &str) -> Result<Vec<&str>, ParseTypeError> {
+    let mut tokens = Vec::new();
+    let mut rest = src;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(tokens);
+        }
+        if let Some(comment) = rest.strip_prefix("//") {
+            rest = comment.split_once('\n').map(|(_, r)| r).unwrap_or("");
+            continue;
+        }
+        if let Some(comment) = rest.strip_prefix("/*") {
+            let (_, r) = comment
+                .split_once("*/")
+                .ok_or_else(|| ParseTypeError::new(rest, "unterminated comment"))?;
+            rest = r;
+            continue;
+        }
+        let len = if rest.starts_with("->") {
+            2
+        } else if rest.starts_with(|c: char| "{}()<>,:;=_@/.".contains(c)) {
+            1
+        } else {
+            rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '%'))
+                .unwrap_or(rest.len())
+        };
+        if len == 0 {
+            return Err(ParseTypeError::new(rest, "unexpected character"));
+        }
+        tokens.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+}
+
+impl<'s> Parser<'s> {
+    fn new(src: &'s str) -> Result<Self, ParseTypeError> {
+        Ok(Self {
+            src,
+            tokens: tokenize(src)?,
+            pos: 0,
+            defs: Vec::new(),
+        })
+    }
+
+    fn error(&self, reason: impl Into<String>) -> ParseTypeError {
+        match self.tokens.get(self.pos) {
+            Some(token) => ParseTypeError::new(token, reason),
+            None => ParseTypeError::new(self.src, format!("{} at the end", reason.into())),
+        }
+    }
+
+    fn peek(&self) -> Option<&'s str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<&'s str, ParseTypeError> {
+        let token = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseTypeError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{token}`")))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, ParseTypeError> {
+        let token = self.peek().ok_or_else(|| self.error("expected a name"))?;
+        let name = token.strip_prefix('%').unwrap_or(token);
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return Err(self.error("expected a name"));
+        }
+        self.pos += 1;
+        Ok(name.to_string())
+    }
+
+    // Parses `{ item, item, ... }` with an optional trailing comma.
+    fn braced<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, ParseTypeError>,
+    ) -> Result<Vec<T>, ParseTypeError> {
+        self.expect("{")?;
+        let mut items = Vec::new();
+        while !self.eat("}") {
+            items.push(item(self)?);
+            if !self.eat(",") {
+                self.expect("}")?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn interface(mut self) -> Result<WitInterface, ParseTypeError> {
+        // Skips `package ns:name@version;`.
+        if self.eat("package") {
+            while self.next()? != ";" {}
+        }
+        self.expect("interface")?;
+        let name = self.ident()?;
+        self.expect("{")?;
+        let mut funcs = Vec::new();
+        while !self.eat("}") {
+            match self.peek() {
+                Some("record") => {
+                    self.pos += 1;
+                    let name = self.ident()?;
+                    let fields = self.braced(|p| {
+                        let field = p.ident()?;
+                        p.expect(":")?;
+                        Ok((field, p.ty()?))
+                    })?;
+                    self.defs.push((name, TypeDef::Record(fields)));
+                }
+                Some("variant") => {
+                    self.pos += 1;
+                    let name = self.ident()?;
+                    let cases = self.braced(|p| {
+                        let case = p.ident()?;
+                        let payload = if p.eat("(") {
+                            let ty = p.ty()?;
+                            p.expect(")")?;
+                            Some(ty)
+                        } else {
+                            None
+                        };
+                        Ok((case, payload))
+                    })?;
+                    self.defs.push((name, TypeDef::Variant(cases)));
+                }
+                Some("enum") => {
+                    self.pos += 1;
+                    let name = self.ident()?;
+                    let cases = self.braced(Self::ident)?;
+                    self.defs
+                        .push((name, TypeDef::Resolved(WitType::Enum(cases))));
+                }
+                Some("flags") => {
+                    self.pos += 1;
+                    let name = self.ident()?;
+                    let flags = self.braced(Self::ident)?;
+                    if flags.len() > MAX_FLAGS {
+                        return Err(self.error("at most 32 flags are supported"));
+                    }
+                    self.defs
+                        .push((name, TypeDef::Resolved(WitType::Flags(flags))));
+                }
+                Some("type") => {
+                    self.pos += 1;
+                    let name = self.ident()?;
+                    self.expect("=")?;
+                    let ty = self.ty()?;
+                    self.expect(";")?;
+                    self.defs.push((name, TypeDef::Alias(ty)));
+                }
+                Some("use") | Some("resource") => {
+                    return Err(self.error("is not supported"));
+                }
+                _ => {
+                    let name = self.ident()?;
+                    self.expect(":")?;
+                    self.expect("func")?;
+                    self.expect("(")?;
+                    let mut params = Vec::new();
+                    while !self.eat(")") {
+                        let param = self.ident()?;
+                        self.expect(":")?;
+                        params.push((param, self.ty()?));
+                        if !self.eat(",") {
+                            self.expect(")")?;
+                            break;
+                        }
+                    }
+                    let result = if self.eat("->") {
+                        Some(self.ty()?)
+                    } else {
+                        None
+                    };
+                    self.expect(";")?;
+                    funcs.push((name, params, result));
+                }
+            }
+        }
+
+        let mut types = Vec::new();
+        for (name, _) in &self.defs {
+            types.push((name.clone(), self.resolve_named(name, &mut Vec::new())?));
+        }
+        let funcs = funcs
+            .into_iter()
+            .map(|(name, params, result)| {
+                Ok(WitFunc {
+                    name,
+                    params: params
+                        .into_iter()
+                        .map(|(name, ty)| Ok((name, self.resolve(&ty, &mut Vec::new())?)))
+                        .collect::<Result<_, ParseTypeError>>()?,
+                    result: result
+                        .map(|ty| self.resolve(&ty, &mut Vec::new()))
+                        .transpose()?,
+                })
+            })
+            .collect::<Result<_, ParseTypeError>>()?;
+        Ok(WitInterface { name, types, funcs })
+    }
+
+    fn ty(&mut self) -> Result<TypeRef, ParseTypeError> {
+        let token = self.next()?;
+        let prim = match token {
+            "bool" => Some(WitType::Bool),
+            "s8" => Some(WitType::S8),
+            "u8" => Some(WitType::U8),
+            "s16" => Some(WitType::S16),
+            "u16" => Some(WitType::U16),
+            "s32" => Some(WitType::S32),
+            "u32" => Some(WitType::U32),
+            "s64" => Some(WitType::S64),
+            "u64" => Some(WitType::U64),
+            "f32" | "float32" => Some(WitType::F32),
+            "f64" | "float64" => Some(WitType::F64),
+            "char" => Some(WitType::Char),
+            "string" => Some(WitType::String),
+            _ => None,
+        };
+        if let Some(prim) = prim {
+            return Ok(TypeRef::Resolved(prim));
+        }
+        let ty = match token {
+            "list" => {
+                self.expect("<")?;
+                let ty = self.ty()?;
+                self.expect(">")?;
+                TypeRef::List(Box::new(ty))
+            }
+            "option" => {
+                self.expect("<")?;
+                let ty = self.ty()?;
+                self.expect(">")?;
+                TypeRef::Option(Box::new(ty))
+            }
+            "tuple" => {
+                self.expect("<")?;
+                let mut tys = vec![self.ty()?];
+                while self.eat(",") {
+                    tys.push(self.ty()?);
+                }
+                self.expect(">")?;
+                TypeRef::Tuple(tys)
+            }
+            "result" => {
+                if !self.eat("<") {
+                    return Ok(TypeRef::Result(None, None));
+                }
+                let ok = if self.eat("_") {
+                    None
+                } else {
+                    Some(Box::new(self.ty()?))
+                };
+                let err = if self.eat(",") {
+                    Some(Box::new(self.ty()?))
+                } else {
+                    None
+                };
+                self.expect(">")?;
+                TypeRef::Result(ok, err)
+            }
+            _ => {
+                self.pos -= 1;
+                TypeRef::Named(self.ident()?)
+            }
+        };
+        Ok(ty)
+    }
+
+    fn resolve_named(
+        &self,
+        name: &str,
+        stack: &mut Vec<String>,
+    ) -> Result<WitType, ParseTypeError> {
+        if stack.iter().any(|n| n == name) {
+            return Err(ParseTypeError::new(
+                name,
+                "recursive types are not supported",
+            ));
+        }
+        let (_, def) = self
+            .defs
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or_else(|| ParseTypeError::new(name, "unknown type"))?;
+        stack.push(name.to_string());
+        let ty = match def {
+            TypeDef::Alias(ty) => self.resolve(ty, stack)?,
+            TypeDef::Record(fields) => WitType::Record(
+                fields
+                    .iter()
+                    .map(|(n, ty)| Ok((n.clone(), self.resolve(ty, stack)?)))
+                    .collect::<Result<_, ParseTypeError>>()?,
+            ),
+            TypeDef::Variant(cases) => WitType::Variant(
+                cases
+                    .iter()
+                    .map(|(n, ty)| {
+                        let ty = ty.as_ref().map(|ty| self.resolve(ty, stack)).transpose()?;
+                        Ok((n.clone(), ty))
+                    })
+                    .collect::<Result<_, ParseTypeError>>()?,
+            ),
+            TypeDef::Resolved(ty) => ty.clone(),
+        };
+        stack.pop();
+        Ok(ty)
+    }
+
+    fn resolve(&self, ty: &TypeRef, stack: &mut Vec<String>) -> Result<WitType, ParseTypeError> {
+        let ty = match ty {
+            TypeRef::Resolved(ty) => ty.clone(),
+            TypeRef::Named(name) => self.resolve_named(name, stack)?,
+            TypeRef::List(ty) => WitType::List(Box::new(self.resolve(ty, stack)?)),
+            TypeRef::Option(ty) => WitType::Option(Box::new(self.resolve(ty, stack)?)),
+            TypeRef::Tuple(tys) => WitType::Tuple(
+                tys.iter()
+                    .map(|ty| self.resolve(ty, stack))
+                    .collect::<Result<_, _>>()?,
+            ),
+            TypeRef::Result(ok, err) => WitType::Result {
+                ok: ok
+                    .as_ref()
+                    .map(|ty| self.resolve(ty, stack).map(Box::new))
+                    .transpose()?,
+                err: err
+                    .as_ref()
+                    .map(|ty| self.resolve(ty, stack).map(Box::new))
+                    .transpose()?,
+            },
+        };
+        Ok(ty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmedge_sys::ffi;
+
+    use super::*;
+    use crate::core::instance::memory::{InnerMemType, MemType};
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn record(fields: &[(&str, WitType)]) -> WitType {
+        WitType::Record(
+            fields
+                .iter()
+                .map(|(name, ty)| (name.to_string(), ty.clone()))
+                .collect(),
+        )
+    }
+
+    fn variant(cases: &[(&str, Option<WitType>)]) -> WitType {
+        WitType::Variant(
+            cases
+                .iter()
+                .map(|(name, ty)| (name.to_string(), ty.clone()))
+                .collect(),
+        )
+    }
+
+    fn memory(pages: u32) -> Memory {
+        let limit = ffi::WasmEdge_Limit {
+            HasMax: true,
+            Shared: false,
+            Min: pages,
+            Max: pages,
+        };
+        let ty = MemType {
+            inner: InnerMemType(unsafe { ffi::WasmEdge_MemoryTypeCreate(limit) }),
+        };
+        Memory::create(ty).unwrap()
+    }
+
+    // Allocates upwards from an address, like a guest allocator which never frees.
+    struct BumpAlloc(u32);
+
+    impl GuestAlloc for BumpAlloc {
+        fn alloc(&mut self, size: u32, align: u32) -> Result<u32, WitError> {
+            let ptr = align_to(self.0, align);
+            self.0 = ptr + size;
+            Ok(ptr)
+        }
+    }
+
+    // Types with a value of each, covering every kind of type.
+    fn samples() -> Vec<(WitType, WitValue)> {
+        let point = record(&[("x", WitType::U8), ("y", WitType::F64)]);
+        let shape = variant(&[
+            ("none", None),
+            ("circle", Some(WitType::F32)),
+            ("named", Some(WitType::String)),
+        ]);
+        vec![
+            (WitType::Bool, WitValue::Bool(true)),
+            (WitType::S8, WitValue::S8(-8)),
+            (WitType::U16, WitValue::U16(0xbeef)),
+            (WitType::S32, WitValue::S32(-32)),
+            (WitType::U64, WitValue::U64(u64::MAX)),
+            (WitType::F32, WitValue::F32(1.5)),
+            (WitType::F64, WitValue::F64(-2.25)),
+            (WitType::Char, WitValue::Char('λ')),
+            (WitType::Flags(vec![]), WitValue::Flags(vec![])),
+            (WitType::String, WitValue::String("héllo".to_string())),
+            (
+                WitType::List(Box::new(WitType::U32)),
+                WitValue::List(vec![WitValue::U32(1), WitValue::U32(2)]),
+            ),
+            (
+                WitType::Tuple(vec![WitType::U8, WitType::String]),
+                WitValue::Tuple(vec![WitValue::U8(7), WitValue::String("t".to_string())]),
+            ),
+            (
+                point.clone(),
+                WitValue::Record(vec![
+                    ("x".to_string(), WitValue::U8(1)),
+                    ("y".to_string(), WitValue::F64(2.0)),
+                ]),
+            ),
+            (
+                WitType::List(Box::new(point)),
+                WitValue::List(vec![WitValue::Record(vec![
+                    ("x".to_string(), WitValue::U8(3)),
+                    ("y".to_string(), WitValue::F64(4.0)),
+                ])]),
+            ),
+            (
+                shape.clone(),
+                WitValue::Variant {
+                    case: "circle".to_string(),
+                    payload: Some(Box::new(WitValue::F32(0.5))),
+                },
+            ),
+            (
+                shape.clone(),
+                WitValue::Variant {
+                    case: "named".to_string(),
+                    payload: Some(Box::new(WitValue::String("sq".to_string()))),
+                },
+            ),
+            (
+                shape,
+                WitValue::Variant {
+                    case: "none".to_string(),
+                    payload: None,
+                },
+            ),
+            (
+                WitType::Enum(names(&["a", "b", "c"])),
+                WitValue::Enum("c".to_string()),
+            ),
+            (
+                WitType::Flags(names(&["r", "w", "x"])),
+                WitValue::Flags(names(&["r", "x"])),
+            ),
+            (
+                WitType::Option(Box::new(WitType::U64)),
+                WitValue::Option(Some(Box::new(WitValue::U64(9)))),
+            ),
+            (
+                WitType::Option(Box::new(WitType::U64)),
+                WitValue::Option(None),
+            ),
+            (
+                WitType::Result {
+                    ok: Some(Box::new(WitType::String)),
+                    err: Some(Box::new(WitType::U8)),
+                },
+                WitValue::Result(Err(Some(Box::new(WitValue::U8(1))))),
+            ),
+            (
+                WitType::Result {
+                    ok: None,
+                    err: None,
+                },
+                WitValue::Result(Ok(None)),
+            ),
+        ]
+    }
+
+    #[test]
+    fn lays_out_records_and_tuples() {
+        let ty = record(&[("a", WitType::U8), ("b", WitType::U32), ("c", WitType::U16)]);
+        assert_eq!((ty.alignment(), ty.size()), (4, 12));
+
+        let ty = WitType::Tuple(vec![WitType::U8, WitType::U64]);
+        assert_eq!((ty.alignment(), ty.size()), (8, 16));
+
+        let inner = WitType::Tuple(vec![WitType::U8, WitType::U16]);
+        let ty = record(&[("a", WitType::U8), ("b", inner)]);
+        assert_eq!((ty.alignment(), ty.size()), (2, 6));
+
+        let ty = record(&[("s", WitType::String), ("b", WitType::Bool)]);
+        assert_eq!((ty.alignment(), ty.size()), (4, 12));
+
+        assert_eq!((record(&[]).alignment(), record(&[]).size()), (1, 0));
+    }
+
+    #[test]
+    fn lays_out_variants() {
+        let ty = variant(&[
+            ("a", None),
+            ("b", Some(WitType::U64)),
+            ("c", Some(WitType::U8)),
+        ]);
+        assert_eq!((ty.alignment(), ty.size()), (8, 16));
+
+        let ty = WitType::Option(Box::new(WitType::U32));
+        assert_eq!((ty.alignment(), ty.size()), (4, 8));
+
+        let ty = WitType::Result {
+            ok: Some(Box::new(WitType::String)),
+            err: Some(Box::new(WitType::U8)),
+        };
+        assert_eq!((ty.alignment(), ty.size()), (4, 12));
+
+        let ty = WitType::Enum((0..300).map(|i| format!("e{i}")).collect());
+        assert_eq!((ty.alignment(), ty.size()), (2, 2));
+
+        let ty = WitType::Result {
+            ok: None,
+            err: None,
+        };
+        assert_eq!((ty.alignment(), ty.size()), (1, 1));
+    }
+
+    #[test]
+    fn lays_out_flags() {
+        let flags = |n: usize| WitType::Flags((0..n).map(|i| format!("f{i}")).collect());
+        for (n, align, size) in [
+            (0, 1, 0),
+            (1, 1, 1),
+            (8, 1, 1),
+            (9, 2, 2),
+            (17, 4, 4),
+            (32, 4, 4),
+        ] {
+            assert_eq!(
+                (flags(n).alignment(), flags(n).size()),
+                (align, size),
+                "{n} flags"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_more_than_32_flags() {
+        let names = |n: usize| (0..n).map(|i| format!("f{i}")).collect::<Vec<_>>();
+        assert_eq!(WitType::flags(names(32)), Ok(WitType::Flags(names(32))));
+        assert_eq!(WitType::flags(names(33)), Err(WitError::TooManyFlags(33)));
+
+        let ty = WitType::Flags(names(33));
+        assert_eq!(
+            ty.flags_bits(&names(33), &names(1)),
+            Err(WitError::TooManyFlags(33))
+        );
+        assert_eq!(
+            flags_from_bits(&names(33), 1),
+            Err(WitError::TooManyFlags(33))
+        );
+    }
+
+    #[test]
+    fn flattens_variants_into_joined_types() {
+        let ty = variant(&[
+            ("a", Some(WitType::U32)),
+            ("b", Some(WitType::F32)),
+            ("c", Some(WitType::U64)),
+        ]);
+        assert_eq!(ty.flat(), vec![ValType::I32, ValType::I64]);
+
+        let ty = variant(&[("a", Some(WitType::F32)), ("b", Some(WitType::F64))]);
+        assert_eq!(ty.flat(), vec![ValType::I32, ValType::I64]);
+
+        let ty = WitType::Option(Box::new(WitType::F32));
+        assert_eq!(ty.flat(), vec![ValType::I32, ValType::F32]);
+
+        assert_eq!(WitType::Flags(vec![]).flat(), vec![]);
+    }
+
+    #[test]
+    fn round_trips_through_memory() {
+        let mut mem = memory(1);
+        let mut alloc = BumpAlloc(1024);
+        for (ty, v) in samples() {
+            let ptr = align_to(alloc.0, ty.alignment());
+            alloc.0 = ptr + ty.size();
+            let mut lowerer = Lowerer {
+                alloc: &mut alloc,
+                mem: &mut mem,
+            };
+            lowerer.store(&ty, &v, ptr).unwrap();
+            let lifted = Lifter { mem: &mem }.load(&ty, ptr).unwrap();
+            assert_eq!(lifted, v, "{ty}");
+        }
+        mem.delete();
+    }
+
+    #[test]
+    fn round_trips_through_flat_values() {
+        let mut mem = memory(1);
+        let mut alloc = BumpAlloc(1024);
+        for (ty, v) in samples() {
+            let mut flat = Vec::new();
+            let mut lowerer = Lowerer {
+                alloc: &mut alloc,
+                mem: &mut mem,
+            };
+            lowerer.lower_flat(&ty, &v, &mut flat).unwrap();
+            let tys: Vec<ValType> = flat.iter().map(WasmVal::ty).collect();
+            assert_eq!(tys, ty.flat(), "{ty}");
+            let lifted = Lifter { mem: &mem }
+                .lift_flat(&ty, &mut FlatReader::new(flat))
+                .unwrap();
+            assert_eq!(lifted, v, "{ty}");
+        }
+        mem.delete();
+    }
+
+    #[test]
+    fn rejects_buffers_out_of_memory() {
+        let mem = memory(1);
+        let lifter = Lifter { mem: &mem };
+        let list = WitType::List(Box::new(WitType::U64));
+        assert_eq!(
+            lifter.load_buffer(&list, 0, 8 * 1024 + 1),
+            Err(out_of_bounds())
+        );
+        assert_eq!(
+            lifter.load_buffer(&list, u32::MAX - 7, u32::MAX),
+            Err(out_of_bounds())
+        );
+        assert_eq!(
+            lifter.load_buffer(&WitType::String, 65535, 2),
+            Err(out_of_bounds())
+        );
+        assert_eq!(
+            lifter.load_buffer(&list, 0, 8 * 1024),
+            Ok(WitValue::List(vec![WitValue::U64(0); 8 * 1024]))
+        );
+        mem.delete();
+    }
+
+    #[test]
+    fn caps_lists_of_zero_size_items() {
+        let mem = memory(1);
+        let lifter = Lifter { mem: &mem };
+        let list = WitType::List(Box::new(record(&[])));
+        assert_eq!(
+            lifter.load_buffer(&list, 0, u32::MAX),
+            Err(WitError::ListTooLong(u32::MAX))
+        );
+        assert_eq!(
+            lifter.load_buffer(&list, 0, 3),
+            Ok(WitValue::List(vec![WitValue::Record(vec![]); 3]))
+        );
+        mem.delete();
+    }
+}
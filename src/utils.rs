@@ -1,6 +1,6 @@
 use crate::error::{
     CoreCommonError, CoreError, CoreExecutionError, CoreInstantiationError, CoreLoadError,
-    CoreValidationError,
+    CoreValidationError, HostFailure,
 };
 
 use wasmedge_sys::ffi::{self, WasmEdge_Result, WasmEdge_ResultGetCode, WasmEdge_ResultOK};
@@ -23,230 +23,202 @@ pub(crate) fn check(result: WasmEdge_Result) -> Result<(), CoreError> {
     }
 }
 
+// The codes of the WASM category of WasmEdge results. Both directions of the mapping are
+// generated from it, and `unreachable_patterns` is denied so that a code or an error can't be
+// listed twice.
+macro_rules! result_codes {
+    ($($code:literal => $variant:ident $(($kind:path))?,)*) => {
+        impl CoreError {
+            /// Returns the code of the error in the WASM category of WasmEdge results, or `None`
+            /// for a user error.
+            ///
+            /// [CoreError::Exit] has the code of [CoreCommonError::Terminated].
+            #[deny(unreachable_patterns)]
+            pub fn code(&self) -> Option<u32> {
+                match self {
+                    $(CoreError::$variant $(($kind))? => Some($code),)*
+                    CoreError::Exit(_) => Some(0x01),
+                    CoreError::User(_) | CoreError::Host(_) => None,
+                }
+            }
+
+            /// Returns the error of a code in the WASM category of WasmEdge results, or `None`
+            /// for success (`0x00`) and unknown codes.
+            #[deny(unreachable_patterns)]
+            pub fn from_code(code: u32) -> Option<Self> {
+                match code {
+                    $($code => Some(CoreError::$variant $(($kind))?),)*
+                    _ => None,
+                }
+            }
+        }
+
+        #[cfg(test)]
+        const RESULT_CODES: &[u32] = &[$($code),*];
+    };
+}
+
+result_codes! {
+    // Common errors
+    0x01 => Common(CoreCommonError::Terminated),
+    0x02 => Common(CoreCommonError::RuntimeError),
+    0x03 => Common(CoreCommonError::CostLimitExceeded),
+    0x04 => Common(CoreCommonError::WrongVMWorkflow),
+    0x05 => Common(CoreCommonError::FuncNotFound),
+    0x06 => Common(CoreCommonError::AOTDisabled),
+    0x07 => Common(CoreCommonError::Interrupted),
+    0x08 => Common(CoreCommonError::NotValidated),
+    0x09 => Common(CoreCommonError::UserDefError),
+
+    // sync wasmedge
+    0x0A => Asyncify,
+    0x0B => Yield,
+
+    // Load phase
+    0x20 => Load(CoreLoadError::IllegalPath),
+    0x21 => Load(CoreLoadError::ReadError),
+    0x22 => Load(CoreLoadError::UnexpectedEnd),
+    0x23 => Load(CoreLoadError::MalformedMagic),
+    0x24 => Load(CoreLoadError::MalformedVersion),
+    0x25 => Load(CoreLoadError::MalformedSection),
+    0x26 => Load(CoreLoadError::SectionSizeMismatch),
+    0x27 => Load(CoreLoadError::NameSizeOutOfBounds),
+    0x28 => Load(CoreLoadError::JunkSection),
+    0x29 => Load(CoreLoadError::IncompatibleFuncCode),
+    0x2A => Load(CoreLoadError::IncompatibleDataCount),
+    0x2B => Load(CoreLoadError::DataCountRequired),
+    0x2C => Load(CoreLoadError::MalformedImportKind),
+    0x2D => Load(CoreLoadError::MalformedExportKind),
+    0x2E => Load(CoreLoadError::ExpectedZeroByte),
+    0x2F => Load(CoreLoadError::InvalidMut),
+    0x30 => Load(CoreLoadError::TooManyLocals),
+    0x31 => Load(CoreLoadError::MalformedValType),
+    0x32 => Load(CoreLoadError::MalformedElemType),
+    0x33 => Load(CoreLoadError::MalformedRefType),
+    0x34 => Load(CoreLoadError::MalformedUTF8),
+    0x35 => Load(CoreLoadError::IntegerTooLarge),
+    0x36 => Load(CoreLoadError::IntegerTooLong),
+    0x37 => Load(CoreLoadError::IllegalOpCode),
+    0x38 => Load(CoreLoadError::IllegalGrammar),
+
+    // Validation phase
+    0x40 => Validation(CoreValidationError::InvalidAlignment),
+    0x41 => Validation(CoreValidationError::TypeCheckFailed),
+    0x42 => Validation(CoreValidationError::InvalidLabelIdx),
+    0x43 => Validation(CoreValidationError::InvalidLocalIdx),
+    0x44 => Validation(CoreValidationError::InvalidFuncTypeIdx),
+    0x45 => Validation(CoreValidationError::InvalidFuncIdx),
+    0x46 => Validation(CoreValidationError::InvalidTableIdx),
+    0x47 => Validation(CoreValidationError::InvalidMemoryIdx),
+    0x48 => Validation(CoreValidationError::InvalidGlobalIdx),
+    0x49 => Validation(CoreValidationError::InvalidElemIdx),
+    0x4A => Validation(CoreValidationError::InvalidDataIdx),
+    0x4B => Validation(CoreValidationError::InvalidRefIdx),
+    0x4C => Validation(CoreValidationError::ConstExprRequired),
+    0x4D => Validation(CoreValidationError::DupExportName),
+    0x4E => Validation(CoreValidationError::ImmutableGlobal),
+    0x4F => Validation(CoreValidationError::InvalidResultArity),
+    0x50 => Validation(CoreValidationError::MultiTables),
+    0x51 => Validation(CoreValidationError::MultiMemories),
+    0x52 => Validation(CoreValidationError::InvalidLimit),
+    0x53 => Validation(CoreValidationError::InvalidMemPages),
+    0x54 => Validation(CoreValidationError::InvalidStartFunc),
+    0x55 => Validation(CoreValidationError::InvalidLaneIdx),
+
+    // Instantiation phase
+    0x60 => Instantiation(CoreInstantiationError::ModuleNameConflict),
+    0x61 => Instantiation(CoreInstantiationError::IncompatibleImportType),
+    0x62 => Instantiation(CoreInstantiationError::UnknownImport),
+    0x63 => Instantiation(CoreInstantiationError::DataSegDoesNotFit),
+    0x64 => Instantiation(CoreInstantiationError::ElemSegDoesNotFit),
+
+    // Execution phase
+    0x80 => Execution(CoreExecutionError::WrongInstanceAddress),
+    0x81 => Execution(CoreExecutionError::WrongInstanceIndex),
+    0x82 => Execution(CoreExecutionError::InstrTypeMismatch),
+    0x83 => Execution(CoreExecutionError::FuncTypeMismatch),
+    0x84 => Execution(CoreExecutionError::DivideByZero),
+    0x85 => Execution(CoreExecutionError::IntegerOverflow),
+    0x86 => Execution(CoreExecutionError::InvalidConvToInt),
+    0x87 => Execution(CoreExecutionError::TableOutOfBounds),
+    0x88 => Execution(CoreExecutionError::MemoryOutOfBounds),
+    0x89 => Execution(CoreExecutionError::Unreachable),
+    0x8A => Execution(CoreExecutionError::UninitializedElement),
+    0x8B => Execution(CoreExecutionError::UndefinedElement),
+    0x8C => Execution(CoreExecutionError::IndirectCallTypeMismatch),
+    0x8D => Execution(CoreExecutionError::HostFuncFailed),
+    0x8E => Execution(CoreExecutionError::RefTypeMismatch),
+    0x8F => Execution(CoreExecutionError::UnalignedAtomicAccess),
+    0x90 => Execution(CoreExecutionError::WaitOnUnsharedMemory),
+}
+
 fn gen_runtime_error(code: u32) -> Result<(), CoreError> {
     match code {
         // Success or terminated (exit and return success)
         0x00 => Ok(()),
-
-        // Common errors
-        0x01 => Err(CoreError::Common(CoreCommonError::Terminated)),
-        0x02 => Err(CoreError::Common(CoreCommonError::RuntimeError)),
-        0x03 => Err(CoreError::Common(CoreCommonError::CostLimitExceeded)),
-        0x04 => Err(CoreError::Common(CoreCommonError::WrongVMWorkflow)),
-        0x05 => Err(CoreError::Common(CoreCommonError::FuncNotFound)),
-        0x06 => Err(CoreError::Common(CoreCommonError::AOTDisabled)),
-        0x07 => Err(CoreError::Common(CoreCommonError::Interrupted)),
-        0x08 => Err(CoreError::Common(CoreCommonError::NotValidated)),
-        0x09 => Err(CoreError::Common(CoreCommonError::UserDefError)),
-        0x0A => Err(CoreError::Asyncify),
-        0x0B => Err(CoreError::Yield),
-
-        // Load phase
-        0x20 => Err(CoreError::Load(CoreLoadError::IllegalPath)),
-        0x21 => Err(CoreError::Load(CoreLoadError::ReadError)),
-        0x22 => Err(CoreError::Load(CoreLoadError::UnexpectedEnd)),
-        0x23 => Err(CoreError::Load(CoreLoadError::MalformedMagic)),
-        0x24 => Err(CoreError::Load(CoreLoadError::MalformedVersion)),
-        0x25 => Err(CoreError::Load(CoreLoadError::MalformedSection)),
-        0x26 => Err(CoreError::Load(CoreLoadError::SectionSizeMismatch)),
-        0x27 => Err(CoreError::Load(CoreLoadError::NameSizeOutOfBounds)),
-        0x28 => Err(CoreError::Load(CoreLoadError::JunkSection)),
-        0x29 => Err(CoreError::Load(CoreLoadError::IncompatibleFuncCode)),
-        0x2A => Err(CoreError::Load(CoreLoadError::IncompatibleDataCount)),
-        0x2B => Err(CoreError::Load(CoreLoadError::DataCountRequired)),
-        0x2C => Err(CoreError::Load(CoreLoadError::MalformedImportKind)),
-        0x2D => Err(CoreError::Load(CoreLoadError::MalformedExportKind)),
-        0x2E => Err(CoreError::Load(CoreLoadError::ExpectedZeroByte)),
-        0x2F => Err(CoreError::Load(CoreLoadError::InvalidMut)),
-        0x30 => Err(CoreError::Load(CoreLoadError::TooManyLocals)),
-        0x31 => Err(CoreError::Load(CoreLoadError::MalformedValType)),
-        0x32 => Err(CoreError::Load(CoreLoadError::MalformedElemType)),
-        0x33 => Err(CoreError::Load(CoreLoadError::MalformedRefType)),
-        0x34 => Err(CoreError::Load(CoreLoadError::MalformedUTF8)),
-        0x35 => Err(CoreError::Load(CoreLoadError::IntegerTooLarge)),
-        0x36 => Err(CoreError::Load(CoreLoadError::IntegerTooLong)),
-        0x37 => Err(CoreError::Load(CoreLoadError::IllegalOpCode)),
-        0x38 => Err(CoreError::Load(CoreLoadError::IllegalGrammar)),
-
-        // Validation phase
-        0x40 => Err(CoreError::Validation(CoreValidationError::InvalidAlignment)),
-        0x41 => Err(CoreError::Validation(CoreValidationError::TypeCheckFailed)),
-        0x42 => Err(CoreError::Validation(CoreValidationError::InvalidLabelIdx)),
-        0x43 => Err(CoreError::Validation(CoreValidationError::InvalidLocalIdx)),
-        0x44 => Err(CoreError::Validation(
-            CoreValidationError::InvalidFuncTypeIdx,
-        )),
-        0x45 => Err(CoreError::Validation(CoreValidationError::InvalidFuncIdx)),
-        0x46 => Err(CoreError::Validation(CoreValidationError::InvalidTableIdx)),
-        0x47 => Err(CoreError::Validation(CoreValidationError::InvalidMemoryIdx)),
-        0x48 => Err(CoreError::Validation(CoreValidationError::InvalidGlobalIdx)),
-        0x49 => Err(CoreError::Validation(CoreValidationError::InvalidElemIdx)),
-        0x4A => Err(CoreError::Validation(CoreValidationError::InvalidDataIdx)),
-        0x4B => Err(CoreError::Validation(CoreValidationError::InvalidRefIdx)),
-        0x4C => Err(CoreError::Validation(
-            CoreValidationError::ConstExprRequired,
-        )),
-        0x4D => Err(CoreError::Validation(CoreValidationError::DupExportName)),
-        0x4E => Err(CoreError::Validation(CoreValidationError::ImmutableGlobal)),
-        0x4F => Err(CoreError::Validation(
-            CoreValidationError::InvalidResultArity,
-        )),
-        0x50 => Err(CoreError::Validation(CoreValidationError::MultiTables)),
-        0x51 => Err(CoreError::Validation(CoreValidationError::MultiMemories)),
-        0x52 => Err(CoreError::Validation(CoreValidationError::InvalidLimit)),
-        0x53 => Err(CoreError::Validation(CoreValidationError::InvalidMemPages)),
-        0x54 => Err(CoreError::Validation(CoreValidationError::InvalidStartFunc)),
-        0x55 => Err(CoreError::Validation(CoreValidationError::InvalidLaneIdx)),
-
-        // Instantiation phase
-        0x60 => Err(CoreError::Instantiation(
-            CoreInstantiationError::ModuleNameConflict,
-        )),
-        0x61 => Err(CoreError::Instantiation(
-            CoreInstantiationError::IncompatibleImportType,
-        )),
-        0x62 => Err(CoreError::Instantiation(
-            CoreInstantiationError::UnknownImport,
-        )),
-        0x63 => Err(CoreError::Instantiation(
-            CoreInstantiationError::DataSegDoesNotFit,
-        )),
-        0x64 => Err(CoreError::Instantiation(
-            CoreInstantiationError::ElemSegDoesNotFit,
-        )),
-
-        // Execution phase
-        0x80 => Err(CoreError::Execution(
-            CoreExecutionError::WrongInstanceAddress,
-        )),
-        0x81 => Err(CoreError::Execution(CoreExecutionError::WrongInstanceIndex)),
-        0x82 => Err(CoreError::Execution(CoreExecutionError::InstrTypeMismatch)),
-        0x83 => Err(CoreError::Execution(CoreExecutionError::FuncTypeMismatch)),
-        0x84 => Err(CoreError::Execution(CoreExecutionError::DivideByZero)),
-        0x85 => Err(CoreError::Execution(CoreExecutionError::IntegerOverflow)),
-        0x86 => Err(CoreError::Execution(CoreExecutionError::InvalidConvToInt)),
-        0x87 => Err(CoreError::Execution(CoreExecutionError::TableOutOfBounds)),
-        0x88 => Err(CoreError::Execution(CoreExecutionError::MemoryOutOfBounds)),
-        0x89 => Err(CoreError::Execution(CoreExecutionError::Unreachable)),
-        0x8A => Err(CoreError::Execution(
-            CoreExecutionError::UninitializedElement,
-        )),
-        0x8B => Err(CoreError::Execution(CoreExecutionError::UndefinedElement)),
-        0x8C => Err(CoreError::Execution(
-            CoreExecutionError::IndirectCallTypeMismatch,
-        )),
-        0x8D => Err(CoreError::Execution(CoreExecutionError::HostFuncFailed)),
-        0x8E => Err(CoreError::Execution(CoreExecutionError::RefTypeMismatch)),
-        0x8F => Err(CoreError::Execution(
-            CoreExecutionError::UnalignedAtomicAccess,
-        )),
-        0x90 => Err(CoreError::Execution(
-            CoreExecutionError::WaitOnUnsharedMemory,
-        )),
-
-        _ => Err(CoreError::Common(CoreCommonError::RuntimeError)),
+        code => Err(CoreError::from_code(code).unwrap_or_else(CoreError::runtime)),
     }
 }
 
 impl Into<WasmEdge_Result> for CoreError {
     fn into(self) -> WasmEdge_Result {
-        let code = match self {
-            // Common errors
-            CoreError::Common(CoreCommonError::Terminated) => 0x01,
-            CoreError::Common(CoreCommonError::RuntimeError) => 0x02,
-            CoreError::Common(CoreCommonError::CostLimitExceeded) => 0x03,
-            CoreError::Common(CoreCommonError::WrongVMWorkflow) => 0x04,
-            CoreError::Common(CoreCommonError::FuncNotFound) => 0x05,
-            CoreError::Common(CoreCommonError::AOTDisabled) => 0x06,
-            CoreError::Common(CoreCommonError::Interrupted) => 0x07,
-            CoreError::Common(CoreCommonError::NotValidated) => 0x08,
-            CoreError::Common(CoreCommonError::UserDefError) => 0x09,
-
-            // Load phase
-            CoreError::Load(CoreLoadError::IllegalPath) => 0x20,
-            CoreError::Load(CoreLoadError::ReadError) => 0x21,
-            CoreError::Load(CoreLoadError::UnexpectedEnd) => 0x22,
-            CoreError::Load(CoreLoadError::MalformedMagic) => 0x23,
-            CoreError::Load(CoreLoadError::MalformedVersion) => 0x24,
-            CoreError::Load(CoreLoadError::MalformedSection) => 0x25,
-            CoreError::Load(CoreLoadError::SectionSizeMismatch) => 0x26,
-            CoreError::Load(CoreLoadError::NameSizeOutOfBounds) => 0x27,
-            CoreError::Load(CoreLoadError::JunkSection) => 0x28,
-            CoreError::Load(CoreLoadError::IncompatibleFuncCode) => 0x29,
-            CoreError::Load(CoreLoadError::IncompatibleDataCount) => 0x2A,
-            CoreError::Load(CoreLoadError::DataCountRequired) => 0x2B,
-            CoreError::Load(CoreLoadError::MalformedImportKind) => 0x2C,
-            CoreError::Load(CoreLoadError::MalformedExportKind) => 0x2D,
-            CoreError::Load(CoreLoadError::ExpectedZeroByte) => 0x2E,
-            CoreError::Load(CoreLoadError::InvalidMut) => 0x2F,
-            CoreError::Load(CoreLoadError::TooManyLocals) => 0x30,
-            CoreError::Load(CoreLoadError::MalformedValType) => 0x31,
-            CoreError::Load(CoreLoadError::MalformedElemType) => 0x32,
-            CoreError::Load(CoreLoadError::MalformedRefType) => 0x33,
-            CoreError::Load(CoreLoadError::MalformedUTF8) => 0x34,
-            CoreError::Load(CoreLoadError::IntegerTooLarge) => 0x35,
-            CoreError::Load(CoreLoadError::IntegerTooLong) => 0x36,
-            CoreError::Load(CoreLoadError::IllegalOpCode) => 0x37,
-            CoreError::Load(CoreLoadError::IllegalGrammar) => 0x38,
-
-            // Validation phase
-            CoreError::Validation(CoreValidationError::InvalidAlignment) => 0x40,
-            CoreError::Validation(CoreValidationError::TypeCheckFailed) => 0x41,
-            CoreError::Validation(CoreValidationError::InvalidLabelIdx) => 0x42,
-            CoreError::Validation(CoreValidationError::InvalidLocalIdx) => 0x43,
-            CoreError::Validation(CoreValidationError::InvalidFuncTypeIdx) => 0x44,
-            CoreError::Validation(CoreValidationError::InvalidFuncIdx) => 0x45,
-            CoreError::Validation(CoreValidationError::InvalidTableIdx) => 0x46,
-            CoreError::Validation(CoreValidationError::InvalidMemoryIdx) => 0x47,
-            CoreError::Validation(CoreValidationError::InvalidGlobalIdx) => 0x48,
-            CoreError::Validation(CoreValidationError::InvalidElemIdx) => 0x49,
-            CoreError::Validation(CoreValidationError::InvalidDataIdx) => 0x4A,
-            CoreError::Validation(CoreValidationError::InvalidRefIdx) => 0x4B,
-            CoreError::Validation(CoreValidationError::ConstExprRequired) => 0x4C,
-            CoreError::Validation(CoreValidationError::DupExportName) => 0x4D,
-            CoreError::Validation(CoreValidationError::ImmutableGlobal) => 0x4E,
-            CoreError::Validation(CoreValidationError::InvalidResultArity) => 0x4F,
-            CoreError::Validation(CoreValidationError::MultiTables) => 0x50,
-            CoreError::Validation(CoreValidationError::MultiMemories) => 0x51,
-            CoreError::Validation(CoreValidationError::InvalidLimit) => 0x52,
-            CoreError::Validation(CoreValidationError::InvalidMemPages) => 0x53,
-            CoreError::Validation(CoreValidationError::InvalidStartFunc) => 0x54,
-            CoreError::Validation(CoreValidationError::InvalidLaneIdx) => 0x55,
-
-            // Instantiation phase
-            CoreError::Instantiation(CoreInstantiationError::ModuleNameConflict) => 0x60,
-            CoreError::Instantiation(CoreInstantiationError::IncompatibleImportType) => 0x61,
-            CoreError::Instantiation(CoreInstantiationError::UnknownImport) => 0x62,
-            CoreError::Instantiation(CoreInstantiationError::DataSegDoesNotFit) => 0x63,
-            CoreError::Instantiation(CoreInstantiationError::ElemSegDoesNotFit) => 0x64,
-
-            // Execution phase
-            CoreError::Execution(CoreExecutionError::WrongInstanceAddress) => 0x80,
-            CoreError::Execution(CoreExecutionError::WrongInstanceIndex) => 0x81,
-            CoreError::Execution(CoreExecutionError::InstrTypeMismatch) => 0x82,
-            CoreError::Execution(CoreExecutionError::FuncTypeMismatch) => 0x83,
-            CoreError::Execution(CoreExecutionError::DivideByZero) => 0x84,
-            CoreError::Execution(CoreExecutionError::IntegerOverflow) => 0x85,
-            CoreError::Execution(CoreExecutionError::InvalidConvToInt) => 0x86,
-            CoreError::Execution(CoreExecutionError::TableOutOfBounds) => 0x87,
-            CoreError::Execution(CoreExecutionError::MemoryOutOfBounds) => 0x88,
-            CoreError::Execution(CoreExecutionError::Unreachable) => 0x89,
-            CoreError::Execution(CoreExecutionError::UninitializedElement) => 0x8A,
-            CoreError::Execution(CoreExecutionError::UndefinedElement) => 0x8B,
-            CoreError::Execution(CoreExecutionError::IndirectCallTypeMismatch) => 0x8C,
-            CoreError::Execution(CoreExecutionError::HostFuncFailed) => 0x8D,
-            CoreError::Execution(CoreExecutionError::RefTypeMismatch) => 0x8E,
-            CoreError::Execution(CoreExecutionError::UnalignedAtomicAccess) => 0x8F,
-            CoreError::Execution(CoreExecutionError::WaitOnUnsharedMemory) => 0x90,
-            CoreError::User(user_code) => {
-                return unsafe {
-                    ffi::WasmEdge_ResultGen(ffi::WasmEdge_ErrCategory_UserLevelError, user_code)
-                }
-            }
-            // sync wasmedge
-            CoreError::Asyncify => 0x0A,
-            CoreError::Yield => 0x0B,
-        };
-        unsafe { ffi::WasmEdge_ResultGen(ffi::WasmEdge_ErrCategory_WASM, code) }
+        match self {
+            CoreError::User(code) | CoreError::Host(HostFailure { code, .. }) => unsafe {
+                ffi::WasmEdge_ResultGen(ffi::WasmEdge_ErrCategory_UserLevelError, code)
+            },
+            // Only user errors have no code.
+            e => unsafe {
+                ffi::WasmEdge_ResultGen(ffi::WasmEdge_ErrCategory_WASM, e.code().unwrap_or(0x02))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_every_code() {
+        for &code in RESULT_CODES {
+            let e = CoreError::from_code(code).unwrap();
+            assert_eq!(e.code(), Some(code), "{e}");
+            assert_eq!(CoreError::from_code(e.code().unwrap()), Some(e.clone()));
+            assert_eq!(check(e.clone().into()), Err(e));
+        }
+    }
+
+    #[test]
+    fn decodes_only_the_codes_of_the_table() {
+        for code in (0..=0xFF).chain([0x100, 0xFF_FFFF, u32::MAX]) {
+            assert_eq!(
+                CoreError::from_code(code).is_some(),
+                RESULT_CODES.contains(&code),
+                "{code:#x}"
+            );
+        }
+        assert_eq!(gen_runtime_error(0x00), Ok(()));
+        assert_eq!(gen_runtime_error(0xFF), Err(CoreError::runtime()));
+    }
+
+    #[test]
+    fn maps_exit_to_terminated() {
+        let exit = CoreError::exit(3, "fatal");
+        assert_eq!(exit.code(), Some(0x01));
+        assert_eq!(
+            check(exit.into()),
+            Err(CoreError::Common(CoreCommonError::Terminated))
+        );
+    }
+
+    #[test]
+    fn keeps_user_and_host_errors_out_of_the_table() {
+        assert_eq!(CoreError::User(7).code(), None);
+        assert_eq!(CoreError::host(7, "no such model").code(), None);
+        assert_eq!(check(CoreError::User(7).into()), Err(CoreError::User(7)));
+        assert_eq!(
+            check(CoreError::host(7, "no such model").into()),
+            Err(CoreError::User(7))
+        );
     }
 }